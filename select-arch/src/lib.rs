@@ -1,16 +1,217 @@
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use std::env::consts::{ARCH, OS};
 
 static PLATFORM_MARKERS: Lazy<HashMap<&'static str, Vec<&'static str>>> = Lazy::new(|| {
     HashMap::from([
         ("win", vec!["windows", "win"]),
         ("linux", vec!["linux"]),
-        ("darwin", vec!["osx", "darwin"]),
+        ("darwin", vec!["osx", "darwin", "macos"]),
         ("freebsd", vec!["freebsd", "netbsd", "openbsd"]),
     ])
 });
 
-static NON_AMD64_MARKERS: [&'static str; 17] = [
-    "i386", "i686", "arm", "arm64", "386", "ppc64", "armv7", "armv7l", "mips64", "ppc64",
-    "mips64le", "ppc64le", "aarch64", "armhf", "armv7hl",
-];
+static ARCHITECTURE_MARKERS: Lazy<HashMap<&'static str, Vec<&'static str>>> = Lazy::new(|| {
+    HashMap::from([
+        ("x86_64", vec!["x86_64", "amd64", "x64"]),
+        ("aarch64", vec!["aarch64", "arm64", "armv8"]),
+        ("x86", vec!["i386", "i686", "386"]),
+        ("arm", vec!["armv7", "armhf", "arm"]),
+    ])
+});
+
+/// All platform and architecture markers.
+static KNOWN_MARKERS: Lazy<Vec<&'static str>> = Lazy::new(|| {
+    PLATFORM_MARKERS
+        .values()
+        .chain(ARCHITECTURE_MARKERS.values())
+        .flatten()
+        .copied()
+        .collect()
+});
+
+/// Whether `name` contains `marker` at the start of a word, case-insensitively,
+/// and not only as the start of a longer known marker. So `win` is found in
+/// `tool-win64.zip` but not in `tool-darwin.tar.gz`, and `arm` is not found in
+/// `tool-arm64.zip`, which is for `aarch64`.
+pub fn contains_marker(name: &str, marker: &str) -> bool {
+    contains_marker_with_case(name, marker, false)
+}
+
+/// [`contains_marker`], matching the case too if `case_sensitive`.
+pub fn contains_marker_with_case(name: &str, marker: &str, case_sensitive: bool) -> bool {
+    let (name, marker) = if case_sensitive {
+        (name.to_string(), marker.to_string())
+    } else {
+        (name.to_lowercase(), marker.to_lowercase())
+    };
+    let fold = |s: &str| {
+        if case_sensitive {
+            s.to_string()
+        } else {
+            s.to_lowercase()
+        }
+    };
+    let longer: Vec<_> = KNOWN_MARKERS
+        .iter()
+        .map(|known| fold(known))
+        .filter(|known| known.len() > marker.len() && known.starts_with(&marker))
+        .collect();
+    name.match_indices(&marker).any(|(i, _)| {
+        name[..i]
+            .chars()
+            .last()
+            .is_none_or(|c| !c.is_ascii_alphabetic())
+            && !longer.iter().any(|known| name[i..].starts_with(known))
+    })
+}
+
+/// Markers in asset names of the given platform, which is a value of
+/// [`std::env::consts::OS`].
+pub fn platform_markers_of(os: &str) -> Vec<&'static str> {
+    let key = match os {
+        "windows" => "win",
+        "macos" | "ios" => "darwin",
+        "netbsd" | "openbsd" => "freebsd",
+        other => other,
+    };
+    PLATFORM_MARKERS.get(key).cloned().unwrap_or_default()
+}
+
+/// Markers in asset names of platforms other than the given one, leaving out
+/// those shared with it.
+pub fn other_platform_markers_of(os: &str) -> Vec<&'static str> {
+    let own = platform_markers_of(os);
+    let mut markers: Vec<_> = PLATFORM_MARKERS
+        .values()
+        .flatten()
+        .copied()
+        .filter(|marker| !own.contains(marker))
+        .collect();
+    markers.sort_unstable();
+    markers
+}
+
+/// Markers in asset names of the given architecture, which is a value of
+/// [`std::env::consts::ARCH`].
+pub fn architecture_markers_of(arch: &str) -> Vec<&'static str> {
+    ARCHITECTURE_MARKERS.get(arch).cloned().unwrap_or_default()
+}
+
+/// Markers of universal (fat) binaries of the given platform, which run on all
+/// its architectures. Only macOS has them.
+pub fn universal_markers_of(os: &str) -> Vec<&'static str> {
+    match os {
+        "macos" | "ios" => vec!["universal", "universal2", "fat"],
+        _ => vec![],
+    }
+}
+
+/// Markers in asset names of the current platform.
+pub fn platform_markers() -> Vec<&'static str> {
+    let markers = platform_markers_of(OS);
+    if markers.is_empty() {
+        vec![OS]
+    } else {
+        markers
+    }
+}
+
+/// Markers of universal binaries of the current platform.
+pub fn universal_markers() -> Vec<&'static str> {
+    universal_markers_of(OS)
+}
+
+/// Markers in asset names of the current architecture.
+pub fn architecture_markers() -> Vec<&'static str> {
+    let markers = architecture_markers_of(ARCH);
+    if markers.is_empty() {
+        vec![ARCH]
+    } else {
+        markers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_marker() {
+        assert!(contains_marker("tool-win64.zip", "win"));
+        assert!(contains_marker("tool_Windows_x86_64.zip", "windows"));
+        assert!(!contains_marker("tool-x86_64-apple-darwin.tar.gz", "win"));
+        assert!(contains_marker("tool-linux-amd64.tar.gz", "amd64"));
+        assert!(contains_marker("tool-Linux-ARM.tar.gz", "arm"));
+        assert!(!contains_marker_with_case(
+            "tool-Linux-ARM.tar.gz",
+            "arm",
+            true
+        ));
+        assert!(contains_marker_with_case(
+            "tool-Linux-ARM.tar.gz",
+            "ARM",
+            true
+        ));
+        // `arm` is 32-bit, and `arm64` and `armv8` are markers of aarch64.
+        assert!(!contains_marker("tool-linux-arm64.tar.gz", "arm"));
+        assert!(!contains_marker("tool-linux-ARMv8.tar.gz", "arm"));
+        assert!(contains_marker("tool-linux-armv7-arm64.tar.gz", "armv7"));
+        assert!(contains_marker("tool-arm64-linux-arm.tar.gz", "arm"));
+    }
+
+    #[test]
+    fn test_arm_markers() {
+        let markers = architecture_markers_of("arm");
+        let has_any = |name| markers.iter().any(|m| contains_marker(name, m));
+        assert!(has_any("tool-linux-armv7.tar.gz"));
+        assert!(has_any("tool-linux-arm-gnueabihf.tar.gz"));
+        assert!(!has_any("tool-linux-arm64.tar.gz"));
+        assert!(!has_any("tool-linux-aarch64.tar.gz"));
+    }
+
+    #[test]
+    fn test_darwin_and_macos_naming() {
+        let markers = platform_markers_of("macos");
+        for name in [
+            "tool-x86_64-apple-darwin.tar.gz",
+            "tool_osx_amd64.zip",
+            "tool-macos-arm64.tar.gz",
+        ] {
+            assert!(markers.iter().any(|m| contains_marker(name, m)), "{name}");
+        }
+        assert!(!markers
+            .iter()
+            .any(|m| contains_marker("tool-x86_64-unknown-linux-gnu.tar.gz", m)));
+    }
+
+    #[test]
+    fn test_architecture_markers() {
+        let markers = architecture_markers_of("aarch64");
+        assert!(markers.iter().any(|m| contains_marker("tool-arm64.zip", m)));
+        assert!(!markers
+            .iter()
+            .any(|m| contains_marker("tool-x86_64.zip", m)));
+        assert_eq!(architecture_markers_of("unknown"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_other_platform_markers() {
+        let markers = other_platform_markers_of("linux");
+        assert!(markers.contains(&"darwin") && markers.contains(&"windows"));
+        assert!(!markers.contains(&"linux"));
+        assert!(!other_platform_markers_of("macos").contains(&"osx"));
+    }
+
+    #[test]
+    fn test_universal_markers() {
+        let markers = universal_markers_of("macos");
+        assert!(markers
+            .iter()
+            .any(|m| contains_marker("tool-darwin-universal.tar.gz", m)));
+        assert!(markers
+            .iter()
+            .any(|m| contains_marker("tool-macos-universal2.zip", m)));
+        assert!(universal_markers_of("linux").is_empty());
+    }
+}
@@ -1 +1,8 @@
-mod lib;
+fn main() {
+    println!("platform markers: {:?}", select_arch::platform_markers());
+    println!(
+        "architecture markers: {:?}",
+        select_arch::architecture_markers()
+    );
+    println!("universal markers: {:?}", select_arch::universal_markers());
+}
@@ -1,26 +1,275 @@
+use crate::site::Site;
+use clap::builder::RangedU64ValueParser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+use glob::Pattern;
 use std::path::PathBuf;
 
-use clap::{ArgAction, Parser};
-
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None, after_help = r#"Examples:
-urldecoder test/t.md    # decode test/t.md
-urldecoder *.md -e my   # decode all markdown files in current folder except which in `my` folder
-urldecoder *            # decode all files in current folder
+bpm eza                         # search `eza` and resolve its latest asset
+bpm lxl66566/bpm-rs             # resolve the latest asset of the given repo
+bpm lxl66566/bpm-rs@^0.1        # resolve the newest release matching a semver range
+bpm eza bat                     # resolve several packages in a batch
+bpm eza --dry-run --json        # print the resolved plan as json
+bpm eza --json                  # report errors as json on stderr
+bpm eza --list-assets           # show all assets of the latest release
+bpm eza --sort stars            # search by stars, the most starred first
+bpm eza-community/eza@v0.18.0   # pin the repo and release, for reproducible installs
 "#)]
 pub struct Cli {
-    /// Files to convert. It uses glob("**/{file}") to glob given pattern, like python's `rglob`
-    file: PathBuf,
-    /// Show result only without overwrite
+    /// Packages to resolve. Each could be a name to search, a full name like
+    /// `owner/repo`, or a repo url, optionally followed by `@` and a release
+    /// tag, a semver range like `^1.2`, or one of `latest` (default), `stable`
+    /// and `pre`
+    #[arg(required = true)]
+    pub names: Vec<String>,
+    /// Resolve the package only, without touching the disk
     #[arg(short, long)]
-    dry_run: bool,
-    /// Show full error message
+    pub dry_run: bool,
+    /// Print json instead of human-readable messages: the plan with
+    /// `--dry-run`, and each error as a line of json on stderr, followed by a
+    /// summary line listing the resolved and the failed packages if any
+    /// failed. Warnings stay plain text, drop them with `--silent`. All json
+    /// has a `schema_version`, bumped on breaking changes
+    #[arg(long)]
+    pub json: bool,
+    /// Continue with the other packages when one fails, and report all
+    /// failures at the end
     #[arg(short, long)]
-    verbose: bool,
-    /// Exclude file or folder
-    #[arg(short, long, action = ArgAction::Append)]
-    exclude: Vec<PathBuf>,
-    /// Do not decode `%20` to space
+    pub keep_going: bool,
+    /// Use the newest prerelease instead of the latest release when it's newer
+    /// by semver precedence
+    #[arg(long)]
+    pub prefer_newest: bool,
+    /// Use the most recently published release instead of the one marked as
+    /// latest on the site
+    #[arg(long)]
+    pub newest: bool,
+    /// Only consider releases with tags matching this glob, like `v*.*.*` for
+    /// stable tags of projects that don't mark prereleases, or `*-nightly-*`
+    #[arg(long, value_name = "GLOB")]
+    pub release_tag_glob: Option<Pattern>,
+    /// Archive formats to prefer in order, like `tar.zst,tar.xz,tar.gz`. Other
+    /// formats come after them in the default order
+    #[arg(long, value_delimiter = ',')]
+    pub prefer_format: Vec<String>,
+    /// Replace tokens of asset names before matching the platform and
+    /// architecture, like `amd64=x86_64`. Tokens are separated by `-`, `_` and
+    /// `.`; see them with `--explain`
+    #[arg(long, value_delimiter = ',', value_parser = parse_token_map)]
+    pub token_map: Vec<(String, String)>,
+    /// Keep the assets that are obviously for other platforms, like `.deb` on
+    /// Windows, which are dropped before selection and listing by default
+    #[arg(long)]
+    pub all_assets: bool,
+    /// Match the platform and architecture in asset names case-sensitively,
+    /// for projects that tell assets apart by case. Also applies to dropping
+    /// the assets for other platforms, see `--all-assets`
+    #[arg(long)]
+    pub match_case: bool,
+    /// Print all assets of the release and exit, marking the one that would
+    /// be selected
+    #[arg(long)]
+    pub list_assets: bool,
+    /// Explain how the asset is selected, showing the assets dropped by each
+    /// filter and the final order
+    #[arg(long)]
+    pub explain: bool,
+    /// Number of search results shown in the selection menu, with an entry to
+    /// show the rest
+    #[arg(long, default_value_t = 10, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub results: usize,
+    /// The site to search names on, and to find full names like `owner/repo`
+    /// on: `github`, `github-enterprise:<host>`, `gitea:<host>` or `bitbucket`.
+    /// Repo urls of github.com and bitbucket.org don't need it, and those of
+    /// other hosts must be on its host
+    #[arg(long, default_value = "github")]
+    pub source: Site,
+    /// How to sort the search results
+    #[arg(long, value_enum, default_value_t = SortParam::BestMatch)]
+    pub sort: SortParam,
+    /// Order of the search results. Defaults to descending for all sorts
+    /// except `best-match`, which has no order
+    #[arg(long, value_enum)]
+    pub order: Option<Order>,
+    /// Override the user agent sent with requests, e.g. for proxies that
+    /// filter by user agent
+    #[arg(long)]
+    pub user_agent: Option<String>,
+    /// Trust the root certificate in this PEM file, like the one of a
+    /// TLS-inspecting corporate proxy
+    #[arg(long, value_name = "PATH")]
+    pub ca_cert: Option<PathBuf>,
+    /// Don't verify TLS certificates at all. Anyone on the network could
+    /// tamper with the responses, prefer `--ca-cert`
+    #[arg(long)]
+    pub danger_insecure: bool,
+    /// Trace every HTTP request and response, with credentials redacted
     #[arg(long)]
-    escape_space: bool,
+    pub trace_http: bool,
+    /// Never search names, requiring full names like `owner/repo` or repo
+    /// urls. Saves a request and picks exactly the given repo
+    #[arg(long)]
+    pub no_search: bool,
+    /// Take the first search result without prompting. Results may change
+    /// over time, so pin `owner/repo@version` where installs must be
+    /// reproducible
+    #[arg(long)]
+    pub select_first: bool,
+    /// Pick the best match of a search instead of prompting
+    #[arg(long)]
+    pub non_interactive: bool,
+    /// Suppress all output but errors and the requested results, like the
+    /// plan of `--json`
+    #[arg(long)]
+    pub silent: bool,
+    /// Both `--non-interactive` and `--silent`. The three flags only add up,
+    /// none of them turns another off
+    #[arg(short, long)]
+    pub quiet: bool,
+    /// Show full error messages, with the low-level causes like the network
+    /// error
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+/// Parse a `--token-map` item like `amd64=x86_64`.
+fn parse_token_map(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_string(), to.to_string()))
+        }
+        _ => Err(format!("expect `old=new`, found `{s}`")),
+    }
+}
+
+/// Sort of the repo search, see <https://docs.github.com/en/rest/search/search#search-repositories>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortParam {
+    BestMatch,
+    Stars,
+    Forks,
+    HelpWantedIssues,
+    Updated,
+}
+
+/// Order of the repo search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+impl SortParam {
+    /// The value of the `sort` query parameter, or `None` for best match,
+    /// which is the default of the API.
+    pub const fn as_query(self) -> Option<&'static str> {
+        match self {
+            Self::BestMatch => None,
+            Self::Stars => Some("stars"),
+            Self::Forks => Some("forks"),
+            Self::HelpWantedIssues => Some("help-wanted-issues"),
+            Self::Updated => Some("updated"),
+        }
+    }
+}
+
+impl Order {
+    pub const fn as_query(self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
+impl Cli {
+    /// Whether to prompt for a choice, see `--non-interactive`.
+    pub const fn is_interactive(&self) -> bool {
+        !(self.non_interactive || self.quiet)
+    }
+
+    /// Whether to suppress informational output, see `--silent`.
+    pub const fn is_silent(&self) -> bool {
+        self.silent || self.quiet
+    }
+
+    /// Parse the command line and check the combinations of arguments that
+    /// clap can't express, exiting with a usage error on failure.
+    pub fn parse_checked() -> Self {
+        let cli = Self::parse();
+        if let Err(err) = cli.check() {
+            err.exit();
+        }
+        cli
+    }
+
+    fn check(&self) -> Result<(), clap::Error> {
+        if self.sort == SortParam::BestMatch && self.order.is_some() {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                "`--order` can't be used with `--sort best-match`, which has no order",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The order of the search results: the given one, or descending for sorts
+    /// other than best match.
+    pub fn search_order(&self) -> Option<Order> {
+        self.sort.as_query()?;
+        Some(self.order.unwrap_or(Order::Desc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_order() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["bpm", "eza"].iter().chain(args)).unwrap();
+        assert_eq!(parse(&[]).search_order(), None);
+        assert_eq!(
+            parse(&["--sort", "stars"]).search_order(),
+            Some(Order::Desc)
+        );
+        assert_eq!(
+            parse(&["--sort", "updated", "--order", "asc"]).search_order(),
+            Some(Order::Asc)
+        );
+        assert!(parse(&["--order", "asc"]).check().is_err());
+        assert!(parse(&["--sort", "forks", "--order", "asc"])
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_token_map() {
+        let cli =
+            Cli::try_parse_from(["bpm", "eza", "--token-map", "amd64=x86_64,mac=darwin"]).unwrap();
+        assert_eq!(
+            cli.token_map,
+            [
+                ("amd64".to_string(), "x86_64".to_string()),
+                ("mac".to_string(), "darwin".to_string())
+            ]
+        );
+        assert!(Cli::try_parse_from(["bpm", "eza", "--token-map", "amd64"]).is_err());
+    }
+
+    #[test]
+    fn test_quiet() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["bpm", "eza"].iter().chain(args)).unwrap();
+        let cli = parse(&[]);
+        assert!(cli.is_interactive() && !cli.is_silent());
+        let cli = parse(&["--non-interactive"]);
+        assert!(!cli.is_interactive() && !cli.is_silent());
+        let cli = parse(&["--silent"]);
+        assert!(cli.is_interactive() && cli.is_silent());
+        let cli = parse(&["-q"]);
+        assert!(!cli.is_interactive() && cli.is_silent());
+    }
 }
@@ -0,0 +1,423 @@
+use crate::cli::{Order, SortParam};
+use crate::search::{Asset, ReleaseProvider};
+use crate::site::Site;
+use crate::utils::err::MyError;
+use crate::utils::UrlJoinAll;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use log::debug;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, RETRY_AFTER};
+use reqwest::StatusCode;
+use url::Url;
+
+static GITHUB_API_VERSION: &str = "2022-11-28";
+
+/// The parts of a site's API that differ between sites. [`Site::client`]
+/// gives the one of a site, so callers don't match on the site for each
+/// endpoint.
+pub trait ForgeClient {
+    /// Headers sent with every API request to the site.
+    fn headers(&self) -> HeaderMap;
+
+    /// The error of a failed response telling that the rate limit of the site
+    /// is exceeded, by its status and headers.
+    fn rate_limit_error(&self, status: StatusCode, headers: &HeaderMap) -> Option<MyError>;
+
+    /// The url to search repos by `query`, sorted by `sort` in `order` as far
+    /// as the site supports.
+    fn search_url(&self, query: &str, sort: SortParam, order: Option<Order>) -> Result<Url>;
+
+    /// The html urls of the repos in a response of [`ForgeClient::search_url`].
+    fn search_results(&self, data: &serde_json::Value) -> Result<Vec<String>>;
+
+    /// The API url of the repo `owner/repo`, followed by `path` like
+    /// `["contents", "Cargo.toml"]`.
+    fn repo_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url>;
+
+    /// The API url of the releases of `owner/repo`, followed by `path` like
+    /// `["latest"]` or `["tags", tag]`. Without `path`, it's the list of the
+    /// recent releases.
+    fn releases_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url>;
+
+    /// The assets of a release in a response of [`ForgeClient::releases_url`].
+    fn release_assets(&self, release: &serde_json::Value) -> Result<Vec<Asset>>;
+
+    /// The files to download of `owner/repo` on sites without releases, or
+    /// `None` on sites with releases.
+    fn downloads(
+        &self,
+        _provider: &dyn ReleaseProvider,
+        _owner: &str,
+        _repo: &str,
+    ) -> Result<Option<Vec<Asset>>> {
+        Ok(None)
+    }
+}
+
+/// GitHub and GitHub Enterprise.
+pub struct Github {
+    api_base: Url,
+}
+
+/// Gitea and Forgejo.
+pub struct Gitea {
+    api_base: Url,
+}
+
+/// Bitbucket Cloud, which has no repo search and no releases.
+pub struct Bitbucket {
+    api_base: Url,
+}
+
+impl Site {
+    /// The API client of this site.
+    pub fn client(&self) -> Box<dyn ForgeClient> {
+        let api_base = self.api_base();
+        match self {
+            Self::Github | Self::GithubEnterprise { .. } => Box::new(Github { api_base }),
+            Self::Gitea { .. } => Box::new(Gitea { api_base }),
+            Self::Bitbucket => Box::new(Bitbucket { api_base }),
+        }
+    }
+}
+
+/// The html urls of the repos in `items`, an array of repo objects.
+fn html_urls(items: Option<&Vec<serde_json::Value>>) -> Result<Vec<String>> {
+    Ok(items
+        .context("No items found in the response")?
+        .iter()
+        .map(|item| item["html_url"].as_str().unwrap_or_default().to_string())
+        .collect())
+}
+
+/// The url of `owner/repo` in the `collection` of `api_base`, like `repos`,
+/// followed by `path`.
+fn repo_url_in(
+    api_base: &Url,
+    collection: &str,
+    owner: &str,
+    repo: &str,
+    path: &[&str],
+) -> Result<Url> {
+    Ok(api_base.join_all_str(
+        [collection, owner, repo]
+            .into_iter()
+            .chain(path.iter().copied()),
+    )?)
+}
+
+/// The assets of a release of GitHub or Gitea, which are in the same shape.
+fn assets_of(release: &serde_json::Value) -> Result<Vec<Asset>> {
+    serde_json::from_value(release["assets"].clone())
+        .context("Assets API response has no valid array named `assets`")
+}
+
+/// `Accept` json, for sites without a vendor media type.
+fn accept_json() -> HeaderMap {
+    HeaderMap::from_iter([(ACCEPT, HeaderValue::from_static("application/json"))])
+}
+
+/// The rate limit error of a `429` response with a `Retry-After` header in
+/// seconds, as sites without rate limit headers of their own send.
+fn retry_after_error(site: &str, status: StatusCode, headers: &HeaderMap) -> Option<MyError> {
+    if status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let seconds: i64 = headers.get(RETRY_AFTER)?.to_str().ok()?.parse().ok()?;
+    let now = Utc::now().timestamp();
+    Some(MyError::rate_limited(site, now + seconds, now))
+}
+
+impl ForgeClient for Github {
+    fn headers(&self) -> HeaderMap {
+        HeaderMap::from_iter([
+            (
+                ACCEPT,
+                HeaderValue::from_static("application/vnd.github+json"),
+            ),
+            (
+                "X-GitHub-Api-Version"
+                    .try_into()
+                    .expect("valid header name"),
+                HeaderValue::from_static(GITHUB_API_VERSION),
+            ),
+        ])
+    }
+
+    /// GitHub answers `403` or `429` with the time the limit resets in
+    /// `X-RateLimit-Reset`.
+    fn rate_limit_error(&self, status: StatusCode, headers: &HeaderMap) -> Option<MyError> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let exhausted = status == StatusCode::TOO_MANY_REQUESTS
+            || (status == StatusCode::FORBIDDEN && header("x-ratelimit-remaining") == Some("0"));
+        if !exhausted {
+            return None;
+        }
+        let reset = header("x-ratelimit-reset")?.parse().ok()?;
+        Some(MyError::rate_limited(
+            "GitHub",
+            reset,
+            Utc::now().timestamp(),
+        ))
+    }
+
+    fn search_url(&self, query: &str, sort: SortParam, order: Option<Order>) -> Result<Url> {
+        // Search API: https://docs.github.com/zh/rest/search/search?apiVersion=2022-11-28#search-repositories
+        let mut url = self.api_base.join_all_str(["search", "repositories"])?;
+        url.query_pairs_mut()
+            .append_pair("q", &format!("{query} in:name"))
+            .append_pair("page", "1");
+        if let Some(sort) = sort.as_query() {
+            url.query_pairs_mut().append_pair("sort", sort);
+        }
+        if let Some(order) = order {
+            url.query_pairs_mut().append_pair("order", order.as_query());
+        }
+        Ok(url)
+    }
+
+    fn search_results(&self, data: &serde_json::Value) -> Result<Vec<String>> {
+        html_urls(data["items"].as_array())
+    }
+
+    fn repo_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url> {
+        repo_url_in(&self.api_base, "repos", owner, repo, path)
+    }
+
+    fn releases_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url> {
+        let mut url = self.repo_url(owner, repo, &[&["releases"], path].concat())?;
+        if path.is_empty() {
+            url.set_query(Some("per_page=100"));
+        }
+        Ok(url)
+    }
+
+    fn release_assets(&self, release: &serde_json::Value) -> Result<Vec<Asset>> {
+        assets_of(release)
+    }
+}
+
+impl ForgeClient for Gitea {
+    fn headers(&self) -> HeaderMap {
+        accept_json()
+    }
+
+    fn rate_limit_error(&self, status: StatusCode, headers: &HeaderMap) -> Option<MyError> {
+        retry_after_error("Gitea", status, headers)
+    }
+
+    fn search_url(&self, query: &str, sort: SortParam, order: Option<Order>) -> Result<Url> {
+        // Search API: https://gitea.com/api/swagger#/repository/repoSearch
+        let mut url = self.api_base.join_all_str(["repos", "search"])?;
+        url.query_pairs_mut()
+            .append_pair("q", query)
+            .append_pair("limit", "50");
+        // Gitea can't sort by help wanted issues, and has no order without a
+        // sort.
+        if let Some(sort) = sort.as_query().filter(|&sort| sort != "help-wanted-issues") {
+            url.query_pairs_mut().append_pair("sort", sort);
+            if let Some(order) = order {
+                url.query_pairs_mut().append_pair("order", order.as_query());
+            }
+        }
+        Ok(url)
+    }
+
+    fn search_results(&self, data: &serde_json::Value) -> Result<Vec<String>> {
+        html_urls(data["data"].as_array())
+    }
+
+    fn repo_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url> {
+        repo_url_in(&self.api_base, "repos", owner, repo, path)
+    }
+
+    /// Release API: <https://gitea.com/api/swagger#/repository/repoListReleases>
+    fn releases_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url> {
+        let mut url = self.repo_url(owner, repo, &[&["releases"], path].concat())?;
+        if path.is_empty() {
+            url.set_query(Some("limit=50"));
+        }
+        Ok(url)
+    }
+
+    fn release_assets(&self, release: &serde_json::Value) -> Result<Vec<Asset>> {
+        assets_of(release)
+    }
+}
+
+impl ForgeClient for Bitbucket {
+    fn headers(&self) -> HeaderMap {
+        accept_json()
+    }
+
+    fn rate_limit_error(&self, status: StatusCode, headers: &HeaderMap) -> Option<MyError> {
+        retry_after_error("Bitbucket", status, headers)
+    }
+
+    fn search_url(&self, _query: &str, _sort: SortParam, _order: Option<Order>) -> Result<Url> {
+        bail!(
+            "Bitbucket has no repo search, please give a full name like `owner/repo` or a repo url"
+        )
+    }
+
+    fn search_results(&self, _data: &serde_json::Value) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn repo_url(&self, owner: &str, repo: &str, path: &[&str]) -> Result<Url> {
+        repo_url_in(&self.api_base, "repositories", owner, repo, path)
+    }
+
+    fn releases_url(&self, _owner: &str, _repo: &str, _path: &[&str]) -> Result<Url> {
+        bail!("Bitbucket repos have no releases")
+    }
+
+    fn release_assets(&self, _release: &serde_json::Value) -> Result<Vec<Asset>> {
+        bail!("Bitbucket repos have no releases")
+    }
+
+    /// Files in the downloads of the repo.
+    fn downloads(
+        &self,
+        provider: &dyn ReleaseProvider,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Option<Vec<Asset>>> {
+        let mut api = self.repo_url(owner, repo, &["downloads"])?;
+        api.set_query(Some("pagelen=100"));
+        debug!("Get downloads from API: {}", api);
+        let data = provider
+            .get_json(self, api)
+            .context("Failed to get the downloads")?;
+        let assets: Vec<Asset> = data["values"]
+            .as_array()
+            .context("Downloads API response has no array named `values`")?
+            .iter()
+            .map(|file| Asset {
+                name: file["name"].as_str().unwrap_or_default().to_string(),
+                url: file["links"]["self"]["href"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                size: file["size"].as_u64().unwrap_or_default(),
+                ..Default::default()
+            })
+            .collect();
+        if assets.is_empty() {
+            bail!(MyError::NoRelease(format!("{owner}/{repo}")));
+        }
+        Ok(Some(assets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_url() {
+        let url = Site::Github
+            .client()
+            .search_url("eza", SortParam::Stars, Some(Order::Desc))
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://api.github.com/search/repositories?q=eza+in%3Aname&page=1&sort=stars&order=desc"
+        );
+        let gitea = Site::Gitea {
+            host: "git.example.com".into(),
+        };
+        let url = gitea
+            .client()
+            .search_url("eza", SortParam::HelpWantedIssues, Some(Order::Asc))
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://git.example.com/api/v1/repos/search?q=eza&limit=50"
+        );
+        assert!(Site::Bitbucket
+            .client()
+            .search_url("eza", SortParam::BestMatch, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_search_results() {
+        let data = serde_json::json!({ "items": [{ "html_url": "https://github.com/eza-community/eza" }] });
+        assert_eq!(
+            Site::Github.client().search_results(&data).unwrap(),
+            ["https://github.com/eza-community/eza"]
+        );
+        let gitea = Site::Gitea {
+            host: "git.example.com".into(),
+        };
+        assert!(gitea.client().search_results(&data).is_err());
+    }
+
+    #[test]
+    fn test_releases_url() {
+        let url = Site::Github
+            .client()
+            .releases_url("owner", "tool", &["tags", "v1.0.0"])
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://api.github.com/repos/owner/tool/releases/tags/v1.0.0"
+        );
+        let gitea = Site::Gitea {
+            host: "git.example.com".into(),
+        };
+        let url = gitea.client().releases_url("owner", "tool", &[]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://git.example.com/api/v1/repos/owner/tool/releases?limit=50"
+        );
+        let url = Site::Bitbucket
+            .client()
+            .repo_url("owner", "tool", &["downloads"])
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://api.bitbucket.org/2.0/repositories/owner/tool/downloads"
+        );
+        assert!(Site::Bitbucket
+            .client()
+            .releases_url("owner", "tool", &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_headers() {
+        let headers = Site::Github.client().headers();
+        assert_eq!(headers[ACCEPT], "application/vnd.github+json");
+        assert_eq!(headers["x-github-api-version"], GITHUB_API_VERSION);
+        let headers = Site::Bitbucket.client().headers();
+        assert_eq!(headers[ACCEPT], "application/json");
+        assert!(!headers.contains_key("x-github-api-version"));
+    }
+
+    #[test]
+    fn test_rate_limit_error() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1700000000"));
+        let github = Site::Github.client();
+        assert!(github
+            .rate_limit_error(StatusCode::FORBIDDEN, &headers)
+            .is_some());
+        assert!(github
+            .rate_limit_error(StatusCode::NOT_FOUND, &headers)
+            .is_none());
+        let bitbucket = Site::Bitbucket.client();
+        assert!(bitbucket
+            .rate_limit_error(StatusCode::FORBIDDEN, &headers)
+            .is_none());
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("60"));
+        let err = bitbucket
+            .rate_limit_error(StatusCode::TOO_MANY_REQUESTS, &headers)
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Bitbucket API rate limit exceeded; resets in 1 minute (at "));
+    }
+}
@@ -8,22 +8,122 @@
 )]
 
 mod cli;
+mod forge;
 mod search;
+mod site;
 mod utils;
 
-use anyhow::anyhow;
 use anyhow::Result;
-use clap::Parser;
 use cli::Cli;
-use colored::*;
+use colored::Colorize;
+use die_exit::die;
+use log::LevelFilter;
 use once_cell::sync::Lazy;
-use search::RepoHandler;
-use url::Url;
+use search::{HttpProvider, PlanReport, RepoHandler, HTTP_LOG_TARGET, SCHEMA_VERSION};
+use utils::dedup_inputs;
+use utils::err::{error_message, MyError};
 
-static CLI: Lazy<Cli> = Lazy::new(|| Cli::parse());
+static CLI: Lazy<Cli> = Lazy::new(Cli::parse_checked);
+
+/// Report the failure of a package, as json on stderr with `--json`. Only
+/// `--verbose` shows the full chain of causes, see [`error_message`].
+fn report_error(name: &str, err: &anyhow::Error) {
+    let message = error_message(err, CLI.verbose);
+    if CLI.json {
+        let kind = err.downcast_ref::<MyError>().map_or("other", MyError::kind);
+        let report = serde_json::json!({
+            "schema_version": SCHEMA_VERSION,
+            "error": message,
+            "kind": kind,
+            "package": name,
+        });
+        eprintln!("{report}");
+    } else {
+        eprintln!("{}", format!("Failed to resolve `{name}`: {message}").red());
+    }
+}
 
 fn main() -> Result<()> {
-    env_logger::init();
-    RepoHandler::new("eza".into()).ask(false).get_asset();
+    let mut logger = env_logger::Builder::from_default_env();
+    if CLI.is_silent() {
+        logger.filter_level(LevelFilter::Error);
+    }
+    if CLI.trace_http {
+        for module in ["reqwest", "hyper", HTTP_LOG_TARGET] {
+            logger.filter_module(module, LevelFilter::Trace);
+        }
+    }
+    logger.init();
+    let (names, duplicates) = dedup_inputs(CLI.names.clone(), &CLI.source);
+    if !CLI.is_silent() {
+        for name in duplicates {
+            eprintln!("Skipping `{name}`: it is given more than once.");
+        }
+    }
+    let mut repos = Vec::new();
+    let mut resolved_names = Vec::new();
+    let mut failures = Vec::new();
+    for name in names {
+        let resolved =
+            RepoHandler::from_input(&name, &CLI, &HttpProvider::default()).and_then(|repo| {
+                let mut repo = repo
+                    .with_prefer_newest(CLI.prefer_newest)
+                    .with_newest(CLI.newest)
+                    .with_preferred_formats(&CLI.prefer_format)
+                    .with_token_map(&CLI.token_map)
+                    .with_all_assets(CLI.all_assets)
+                    .with_match_case(CLI.match_case)
+                    .with_tag_glob(CLI.release_tag_glob.as_ref());
+                repo.get_asset()?;
+                Ok(repo)
+            });
+        match resolved {
+            Ok(repo) => {
+                repos.push(repo);
+                resolved_names.push(name);
+            }
+            Err(err) => {
+                report_error(&name, &err);
+                if !CLI.keep_going {
+                    std::process::exit(1);
+                }
+                failures.push(name);
+            }
+        }
+    }
+    if CLI.explain {
+        for repo in &repos {
+            println!("{}", repo.to_string().bold());
+            repo.explain_assets();
+        }
+    }
+    if CLI.list_assets {
+        for repo in &repos {
+            println!("{}", repo.to_string().bold());
+            repo.print_assets();
+        }
+    } else if CLI.dry_run && CLI.json {
+        let report = PlanReport::new(repos.iter().map(RepoHandler::plan).collect());
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    if !failures.is_empty() {
+        if CLI.json {
+            let summary = serde_json::json!({
+                "schema_version": SCHEMA_VERSION,
+                "resolved": resolved_names,
+                "failed": failures,
+            });
+            eprintln!("{summary}");
+            std::process::exit(1);
+        }
+        if !resolved_names.is_empty() {
+            eprintln!(
+                "Resolved {} package(s): {}",
+                resolved_names.len(),
+                resolved_names.join(", ")
+            );
+        }
+        die!("Failed packages: {}", failures.join(", "));
+    }
     Ok(())
 }
@@ -1,42 +1,537 @@
+use crate::cli::Cli;
+use crate::forge::ForgeClient;
+use crate::site::Site;
 use crate::utils::err::MyError;
 use crate::utils::filter::{select_list, sort_list, Combination};
-use crate::utils::{fmt_repo_list, UrlJoinAll};
+use crate::utils::{
+    fmt_repo_list, fmt_size, is_ci, map_tokens, parse_version, tokenize, UrlJoinAll,
+};
 use crate::CLI;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use assert2::assert;
+use chrono::DateTime;
 use colored::Colorize;
-use die_exit::{die, Die, DieWith};
+use glob::Pattern;
 use log::{debug, error, info, trace, warn};
 use once_cell::sync::Lazy;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use select_arch::{
+    architecture_markers, architecture_markers_of, contains_marker_with_case,
+    other_platform_markers_of, platform_markers, platform_markers_of, universal_markers,
+    universal_markers_of,
+};
+use semver::VersionReq;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::env::consts::{ARCH, OS};
+use std::ffi::OsStr;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
 use url::Url;
 
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
-static REQUEST_CLIENT: Lazy<reqwest::blocking::Client> = Lazy::new(|| {
-    reqwest::blocking::Client::builder()
-        .user_agent(APP_USER_AGENT)
-        .build()
-        .die("An error occured in building request client.")
+/// The client of all requests. Failing to build it, like with an invalid
+/// `--ca-cert`, fails each request instead of exiting, so the error is
+/// reported like any other.
+static REQUEST_CLIENT: Lazy<Result<reqwest::blocking::Client>> = Lazy::new(|| {
+    if CLI.danger_insecure && !CLI.is_silent() {
+        eprintln!(
+            "{}",
+            "Warning: TLS certificate verification is disabled by `--danger-insecure`, anyone on the network can tamper with the responses."
+                .yellow()
+        );
+    }
+    client_builder(
+        CLI.user_agent.as_deref().unwrap_or(APP_USER_AGENT),
+        CLI.ca_cert.as_deref(),
+        CLI.danger_insecure,
+    )
+    .and_then(|builder| Ok(builder.build()?))
+    .context("Failed to build the request client")
 });
 
+/// The builder of the request client, trusting the extra root certificate at
+/// `ca_cert` in PEM, like the one of a TLS-inspecting proxy. With `insecure`,
+/// certificates are not verified at all.
+fn client_builder(
+    user_agent: &str,
+    ca_cert: Option<&Path>,
+    insecure: bool,
+) -> Result<reqwest::blocking::ClientBuilder> {
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(user_agent)
+        .danger_accept_invalid_certs(insecure);
+    if let Some(path) = ca_cert {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("Failed to read the CA certificate {}", path.display()))?;
+        let cert = reqwest::Certificate::from_pem(&pem)
+            .with_context(|| format!("{} is not a valid PEM certificate", path.display()))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok(builder)
+}
+
+/// Log target of the request tracing enabled by `--trace-http`.
+pub static HTTP_LOG_TARGET: &str = "bpm::http";
+static SENSITIVE_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+
+/// Format headers for logging, with credentials redacted.
+fn redact_headers(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{name}: {value}")
+        })
+        .collect()
+}
+
+/// Send a GET request with `headers` by `client`, and trace the request and
+/// its response status.
+fn http_get(
+    client: &reqwest::blocking::Client,
+    headers: HeaderMap,
+    url: Url,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let request = client.get(url).headers(headers).build()?;
+    trace!(
+        target: HTTP_LOG_TARGET,
+        "{} {} {:?}",
+        request.method(),
+        request.url(),
+        redact_headers(request.headers())
+    );
+    let response = client.execute(request);
+    match &response {
+        Ok(r) => trace!(target: HTTP_LOG_TARGET, "{} <- {}", r.status(), r.url()),
+        Err(e) => trace!(target: HTTP_LOG_TARGET, "request failed: {e}"),
+    }
+    response
+}
+
+/// The error of a failed request to `url`. Failing to connect, like when
+/// offline or the host can't be resolved, is a [`MyError::Unreachable`] with
+/// the low-level error as its source.
+fn request_error(err: reqwest::Error, url: &Url) -> anyhow::Error {
+    if err.is_connect() {
+        let host = url.host_str().unwrap_or_default().to_string();
+        anyhow::Error::new(err).context(MyError::Unreachable(host))
+    } else {
+        anyhow::Error::new(err).context(format!("Error fetching {url}"))
+    }
+}
+
+/// Json responses of successful GET requests in this process, keyed by url.
+static RESPONSE_CACHE: Lazy<Mutex<HashMap<Url, serde_json::Value>>> = Lazy::new(Mutex::default);
+
+/// GET the json at `url` of the API of `forge`, with its headers. Fails on
+/// network errors, rate limiting and unsuccessful statuses
+/// ([`MyError::UnexpectedStatus`]).
+///
+/// A url already fetched in this process is not requested again; the cache
+/// never outlives the process.
+fn get_json(forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value> {
+    if let Some(data) = RESPONSE_CACHE.lock().unwrap().get(&url) {
+        debug!("use cached response of {}", url);
+        return Ok(data.clone());
+    }
+    fetch_json(forge, url)
+}
+
+/// [`get_json`] without looking up the cache, so `url` is always requested.
+/// The response still replaces the cached one.
+fn fetch_json(forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value> {
+    let client = REQUEST_CLIENT.as_ref().map_err(|err| anyhow!("{err:#}"))?;
+    let response =
+        http_get(client, forge.headers(), url.clone()).map_err(|err| request_error(err, &url))?;
+    if !response.status().is_success() {
+        if let Some(err) = forge.rate_limit_error(response.status(), response.headers()) {
+            return Err(err.into());
+        }
+        return Err(MyError::UnexpectedStatus {
+            status: response.status(),
+            url,
+        }
+        .into());
+    }
+    let data: serde_json::Value = response
+        .json()
+        .with_context(|| format!("Response of {url} is not a valid json"))?;
+    RESPONSE_CACHE.lock().unwrap().insert(url, data.clone());
+    Ok(data)
+}
+
+/// Where the API responses of the sites come from. Resolution only reads json
+/// by url through it, so tests can feed canned responses instead of hitting
+/// the network.
+pub trait ReleaseProvider {
+    /// GET the json at `url` of the API of `forge`, failing like [`get_json`]
+    /// does.
+    fn get_json(&self, forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value>;
+
+    /// This provider, but requesting every url instead of reusing responses
+    /// got earlier in the process, for paths that must see the current state
+    /// like updates. Providers without a cache are always fresh.
+    // Only `update_asset` uses it, which has no caller until installs land.
+    #[allow(dead_code)]
+    #[must_use]
+    fn fresh(&self) -> Self
+    where
+        Self: Clone,
+    {
+        self.clone()
+    }
+}
+
+/// The provider of the live APIs, by [`REQUEST_CLIENT`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpProvider {
+    /// Bypass the responses cached in this process, see
+    /// [`ReleaseProvider::fresh`].
+    fresh: bool,
+}
+
+impl ReleaseProvider for HttpProvider {
+    fn get_json(&self, forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value> {
+        if self.fresh {
+            fetch_json(forge, url)
+        } else {
+            get_json(forge, url)
+        }
+    }
+
+    fn fresh(&self) -> Self {
+        Self { fresh: true }
+    }
+}
+
 #[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct RepoHandler {
     name: String,
     bin_name: String,
-    site: &'static str,
+    site: Site,
     repo_name: Option<String>,
     repo_owner: Option<String>,
     asset: Option<String>,
+    /// File name of the selected asset, as given by the release rather than
+    /// parsed from the download url.
+    #[serde(default)]
+    asset_name: Option<String>,
+    /// Id and update time of the selected asset, which tell builds of a
+    /// rolling tag apart.
+    #[serde(default)]
+    asset_id: Option<u64>,
+    #[serde(default)]
+    asset_updated_at: Option<String>,
     version: Option<String>,
+    /// The title of the release, shown instead of the tag `version` if set.
+    #[serde(default)]
+    release_name: Option<String>,
+    /// When the release was published, in RFC 3339.
+    #[serde(default)]
+    published_at: Option<String>,
+    /// The default branch of the repo, like `main` or `develop`, once fetched
+    /// by [`RepoHandler::default_branch`].
+    #[serde(default)]
+    default_branch: Option<String>,
     installed_files: Vec<PathBuf>,
+    /// Archive formats like `tar.zst` to prefer, in order, over the built-in
+    /// order of [`format_rank`].
+    #[serde(skip)]
+    preferred_formats: Vec<String>,
+    /// Tokens of asset names to replace before matching markers, like
+    /// `amd64` to `x86_64`.
+    #[serde(skip)]
+    token_map: Vec<(String, String)>,
+    /// All assets of the fetched release.
+    #[serde(skip)]
+    assets: Vec<Asset>,
     prefer_gnu: bool,
     no_pre: bool,
     one_bin: bool,
+    /// The release to use.
+    #[serde(skip)]
+    release: ReleaseSpec,
+    /// Use the newest prerelease instead of the latest release if it's newer.
+    #[serde(skip)]
+    prefer_newest: bool,
+    /// Use the most recently published release instead of the one marked as
+    /// latest.
+    #[serde(skip)]
+    newest: bool,
+    /// Keep the assets that [`is_foreign_asset`] would drop.
+    #[serde(skip)]
+    all_assets: bool,
+    /// Match platform and architecture markers case-sensitively.
+    #[serde(skip)]
+    match_case: bool,
+    /// Only consider releases with tags matching this glob.
+    #[serde(skip)]
+    tag_glob: Option<Pattern>,
+    /// The glibc version to select assets for, instead of [`HOST_GLIBC`].
+    #[serde(skip)]
+    glibc: Option<(u64, u64)>,
+}
+
+/// Which release of a repo to use, given after `@` in the input, like
+/// `owner/repo@v1.2.0` or `owner/repo@^1.2`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ReleaseSpec {
+    /// The release marked as latest by the site.
+    #[default]
+    Latest,
+    /// The most recently published release that is not a prerelease.
+    Stable,
+    /// The most recently published release, prereleases included.
+    Pre,
+    /// The release of an exact tag.
+    Tag(String),
+    /// The highest semver release matching a range.
+    Range(VersionReq),
+}
+
+impl FromStr for ReleaseSpec {
+    type Err = MyError;
+
+    /// `latest`, `stable` and `pre` are the pseudo-versions, and specs
+    /// starting with one of `^~=<>*` are ranges. Anything else is an exact tag,
+    /// so `1.2.3` means the tag `1.2.3` rather than the range `^1.2.3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "" | "latest" => return Ok(Self::Latest),
+            "stable" => return Ok(Self::Stable),
+            "pre" => return Ok(Self::Pre),
+            _ => {}
+        }
+        if s.starts_with(['^', '~', '=', '<', '>', '*']) {
+            return VersionReq::parse(s)
+                .map(Self::Range)
+                .map_err(|_| MyError::InvalidReleaseSpec(s.to_string()));
+        }
+        Ok(Self::Tag(s.to_string()))
+    }
+}
+
+impl fmt::Display for ReleaseSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => write!(f, "latest"),
+            Self::Stable => write!(f, "stable"),
+            Self::Pre => write!(f, "pre"),
+            Self::Tag(tag) => write!(f, "{tag}"),
+            Self::Range(req) => write!(f, "{req}"),
+        }
+    }
+}
+
+/// What bpm needs of a release.
+#[derive(Debug, Default)]
+struct ReleaseInfo {
+    /// The tag, which identifies the release.
+    tag: Option<String>,
+    /// The title of the release, shown to users. Some projects tag with
+    /// hashes or dates and put the human version here.
+    name: Option<String>,
+    /// When the release was published, in RFC 3339.
+    published_at: Option<String>,
+    assets: Vec<Asset>,
+}
+
+/// A downloadable file of a release.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Asset {
+    pub name: String,
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+    pub size: u64,
+    /// The MIME type reported by the site, like `application/zip`.
+    #[serde(default)]
+    pub content_type: String,
+    #[serde(default)]
+    pub id: u64,
+    /// When the asset was last updated, in RFC 3339.
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// Keep only the most recently updated asset of each name, since a re-uploaded
+/// file can leave a stale asset with the same name. The order of the first
+/// occurrences is kept.
+fn dedup_assets(assets: Vec<Asset>) -> Vec<Asset> {
+    let mut deduped: Vec<Asset> = Vec::with_capacity(assets.len());
+    for asset in assets {
+        match deduped.iter_mut().find(|a| a.name == asset.name) {
+            Some(kept) => {
+                if (&asset.updated_at, asset.id) > (&kept.updated_at, kept.id) {
+                    *kept = asset;
+                }
+            }
+            None => deduped.push(asset),
+        }
+    }
+    deduped
+}
+
+/// Archive format of an asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    /// `.tar` and `.tar.*`
+    Tar,
+    Zip,
+    SevenZip,
+    /// Not a known archive, e.g. a raw binary.
+    Other,
+}
+
+impl ArchiveFormat {
+    /// Judge the format by the extension of the asset name. If the extension
+    /// is not a known archive one, like a raw binary or a version suffix as in
+    /// `tool-1.2`, the `content_type` of the asset decides.
+    fn of(name: &str, content_type: &str) -> Self {
+        let name = name.to_lowercase();
+        let ext = Path::new(&name)
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default();
+        match ext {
+            _ if name.contains(".tar.") || ext == "tar" => return Self::Tar,
+            "zip" => return Self::Zip,
+            "7z" => return Self::SevenZip,
+            _ => {}
+        }
+        match content_type.to_lowercase().as_str() {
+            "application/zip" | "application/x-zip-compressed" => Self::Zip,
+            "application/x-7z-compressed" => Self::SevenZip,
+            "application/x-tar"
+            | "application/x-gtar"
+            | "application/gzip"
+            | "application/x-gzip"
+            | "application/x-xz"
+            | "application/x-bzip2"
+            | "application/zstd" => Self::Tar,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Rank of the archive format of an asset, the lower the more preferred.
+/// Tarballs are preferred on unix and zip on windows, other files come after
+/// them, and `.7z` is the last. See [`ArchiveFormat::of`].
+fn format_rank(name: &str, content_type: &str) -> u8 {
+    match ArchiveFormat::of(name, content_type) {
+        ArchiveFormat::SevenZip => 3,
+        ArchiveFormat::Tar if cfg!(windows) => 1,
+        ArchiveFormat::Tar => 0,
+        ArchiveFormat::Zip if cfg!(windows) => 0,
+        ArchiveFormat::Zip => 1,
+        ArchiveFormat::Other => 2,
+    }
+}
+
+/// The glibc version of the host, like `(2, 36)`, by `ldd --version`. `None`
+/// on other platforms, or on Linux with another libc like musl.
+static HOST_GLIBC: Lazy<Option<(u64, u64)>> = Lazy::new(|| {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let output = std::process::Command::new("ldd")
+        .arg("--version")
+        .output()
+        .ok()?;
+    let version = parse_ldd_version(&String::from_utf8_lossy(&output.stdout));
+    debug!("host glibc: {:?}", version);
+    version
+});
+
+/// The glibc version in the first line of `ldd --version` of glibc, like
+/// `ldd (GNU libc) 2.36`.
+fn parse_ldd_version(output: &str) -> Option<(u64, u64)> {
+    let line = output.lines().next()?;
+    if !line.to_lowercase().contains("libc") {
+        return None;
+    }
+    parse_glibc_version(line.split_whitespace().last()?)
+}
+
+/// The glibc version an asset is built against, like `glibc2.17` or
+/// `glibc-2.31` in its name.
+fn glibc_of_asset(name: &str) -> Option<(u64, u64)> {
+    let name = name.to_lowercase();
+    let rest = &name[name.find("glibc")? + "glibc".len()..];
+    parse_glibc_version(rest.trim_start_matches(['-', '_']))
+}
+
+/// Parse the leading `major.minor` of a glibc version, like `2.17` of
+/// `2.17-x86_64.tar.gz`.
+fn parse_glibc_version(s: &str) -> Option<(u64, u64)> {
+    let mut parts = s.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor: String = parts
+        .next()?
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    Some((major, minor.parse().ok()?))
+}
+
+/// Assets left after each stage of [`RepoHandler::rank_assets`].
+#[derive(Debug)]
+pub struct RankStages {
+    pub input: Vec<Asset>,
+    /// Left by [`RepoHandler::prefilter_assets`], or all of `input` with
+    /// `--all-assets`.
+    pub prefilter: Vec<Asset>,
+    pub platform: Vec<Asset>,
+    pub architecture: Vec<Asset>,
+    pub ranked: Vec<Asset>,
+}
+
+/// The version of all json output, bumped when a field is removed or changes
+/// its meaning. Adding fields is not a breaking change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The json printed by `--dry-run --json`: the plans of all resolved packages,
+/// with the [`SCHEMA_VERSION`].
+#[derive(Serialize, Debug)]
+pub struct PlanReport<'a> {
+    schema_version: u32,
+    plans: Vec<Plan<'a>>,
+}
+
+impl<'a> PlanReport<'a> {
+    pub const fn new(plans: Vec<Plan<'a>>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            plans,
+        }
+    }
+}
+
+/// A machine-readable plan of what an install would do, printed by
+/// `--dry-run --json`.
+#[derive(Serialize, Debug)]
+pub struct Plan<'a> {
+    repo: String,
+    url: Option<String>,
+    version: Option<&'a str>,
+    release_name: Option<&'a str>,
+    published_at: Option<&'a str>,
+    asset: Option<&'a str>,
+    asset_name: Option<&'a str>,
+    /// The installed files, absent until the repo is installed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<&'a [PathBuf]>,
 }
 
 // fn filter_assets(assets: Vec<&str>) -> &str {}
@@ -51,25 +546,148 @@ impl RepoHandler {
         );
         Self {
             #[cfg(not(windows))]
-            name: name.clone(),
-            #[cfg(windows)]
-            name,
-            #[cfg(not(windows))]
-            bin_name: name,
+            bin_name: name.clone(),
             #[cfg(windows)]
             bin_name: "*.exe".into(),
-            site: "github",
+            name,
+            site: Site::Github,
             repo_name: None,
             repo_owner: None,
             asset: None,
+            asset_name: None,
+            asset_id: None,
+            asset_updated_at: None,
             version: None,
+            release_name: None,
+            published_at: None,
+            default_branch: None,
             installed_files: Vec::new(),
+            preferred_formats: Vec::new(),
+            token_map: Vec::new(),
+            assets: Vec::new(),
             prefer_gnu: false,
             no_pre: false,
             one_bin: false,
+            release: ReleaseSpec::Latest,
+            prefer_newest: false,
+            newest: false,
+            all_assets: false,
+            match_case: false,
+            tag_glob: None,
+            glibc: None,
         }
     }
 
+    /// Construct a handler from user input, which could be a repo url, a full
+    /// name like `owner/repo`, or a name to search, optionally followed by
+    /// `@` and a [`ReleaseSpec`]. Names are searched on `--source` through
+    /// `provider`, and full names are on it too, unless the site is known from
+    /// the url.
+    ///
+    /// With `--no-search`, a name to search is an error instead. Searching in
+    /// CI is warned, as the results change over time.
+    pub fn from_input(input: &str, cli: &Cli, provider: &impl ReleaseProvider) -> Result<Self> {
+        let repo = Self::parse_input(input, &cli.source)?;
+        if repo.repo_owner.is_some() {
+            return Ok(repo);
+        }
+        if cli.no_search {
+            bail!(MyError::SearchDisabled(repo.name));
+        }
+        let name = repo.name.clone();
+        let repo = repo.ask(cli, provider)?;
+        if !cli.is_silent() && is_ci(std::env::var("CI").ok().as_deref()) {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: `{name}` is resolved to {} by searching, which may change over time. Pin `{}@<version>` for reproducible installs.",
+                    repo.full_name(),
+                    repo.full_name()
+                )
+                .yellow()
+            );
+        }
+        Ok(repo)
+    }
+
+    /// Parse user input like [`RepoHandler::from_input`] without searching,
+    /// so a name to search gives a handler without `repo_owner`.
+    pub fn parse_input(input: &str, source: &Site) -> Result<Self> {
+        let (input, release) = match input.rsplit_once('@') {
+            Some((input, spec)) if !input.is_empty() && !spec.contains('/') => {
+                (input, spec.parse()?)
+            }
+            _ => (input, ReleaseSpec::Latest),
+        };
+        let url = Url::parse(input).ok();
+        let site = url
+            .as_ref()
+            .map(|url| Site::of_url(url, source))
+            .transpose()?;
+        let full_name = url.map_or_else(|| input.to_string(), |url| url.path().to_string());
+        let release = match release {
+            ReleaseSpec::Latest => release_of_path(&full_name).unwrap_or_default(),
+            release => release,
+        };
+        let repo = if full_name.contains('/') {
+            let parsed = Self::default()
+                .set_by_fullname(&full_name)
+                .map_err(|_| MyError::InvalidFullName(input.to_string()))?;
+            let name = parsed.repo_name.clone().unwrap_or_default();
+            Self {
+                repo_owner: parsed.repo_owner,
+                repo_name: parsed.repo_name,
+                ..Self::new(name)
+            }
+        } else {
+            Self::new(input.into())
+        };
+        let site = site.unwrap_or_else(|| source.clone());
+        Ok(Self {
+            site,
+            release,
+            ..repo
+        })
+    }
+
+    pub const fn with_prefer_newest(mut self, prefer_newest: bool) -> Self {
+        self.prefer_newest = prefer_newest;
+        self
+    }
+
+    pub const fn with_all_assets(mut self, all_assets: bool) -> Self {
+        self.all_assets = all_assets;
+        self
+    }
+
+    pub const fn with_match_case(mut self, match_case: bool) -> Self {
+        self.match_case = match_case;
+        self
+    }
+
+    pub fn with_tag_glob(mut self, tag_glob: Option<&Pattern>) -> Self {
+        self.tag_glob = tag_glob.cloned();
+        self
+    }
+
+    pub const fn with_newest(mut self, newest: bool) -> Self {
+        self.newest = newest;
+        self
+    }
+
+    pub fn with_preferred_formats(mut self, formats: &[String]) -> Self {
+        self.preferred_formats = formats
+            .iter()
+            .map(|format| format.trim_start_matches('.').to_string())
+            .collect();
+        self
+    }
+
+    pub fn with_token_map(mut self, token_map: &[(String, String)]) -> Self {
+        self.token_map = token_map.to_vec();
+        self
+    }
+
     pub fn with_bin_name(mut self, bin_name: String) -> Self {
         #[cfg(windows)]
         {
@@ -89,29 +707,17 @@ impl RepoHandler {
         self
     }
 
-    pub fn url(&self) -> Url {
-        assert!(
-            self.repo_name.is_some() || self.repo_owner.is_some(),
-            "repo_name and repo_owner must be set"
-        );
+    /// The url of the repo, or `None` if its owner or name is unknown, like a
+    /// package installed from a local file.
+    pub fn url(&self) -> Option<Url> {
         self.base()
-            .join_all_str([
-                self.repo_owner.as_deref().unwrap(),
-                self.repo_name.as_deref().unwrap(),
-            ])
-            .die_with(|e| format!("trying to construct an invalid url. Err: {e}"))
+            .join_all_str([self.repo_owner.as_deref()?, self.repo_name.as_deref()?])
+            .ok()
     }
 
-    /// use Github as default.
-    #[allow(clippy::unused_self)]
+    /// The base url of the site hosting the repo.
     pub fn base(&self) -> Url {
-        Url::parse("https://github.com").expect("hardcoded URL should be valid")
-    }
-
-    /// use Github as default.
-    #[allow(clippy::unused_self)]
-    pub fn api_base(&self) -> Url {
-        Url::parse("https://api.github.com").expect("hardcoded URL should be valid")
+        self.site.base()
     }
 
     pub fn dedup_file_list(&mut self) {
@@ -127,224 +733,815 @@ impl RepoHandler {
 
     /// Set the `repo_name` and `repo_owner` by fullname.
     /// For example, with the full name `me/myrepo`, the `repo_owner` would be
-    /// `me`, and the `repo_name` would be `myrepo`.
-    #[allow(clippy::unwrap_used)]
-    pub fn set_by_fullname(mut self, full_name: &str) -> Self {
+    /// `me`, and the `repo_name` would be `myrepo`. A trailing `.git` of the
+    /// repo name is stripped, and the parts after the 2nd one, like
+    /// `/releases/tag/v1.0` or `/tree/main`, are ignored.
+    pub fn set_by_fullname(mut self, full_name: &str) -> Result<Self> {
         let mut iter = full_name.trim_matches('/').split('/');
-        self.repo_owner = Some(
-            iter.next()
-                .unwrap_or_else(|| die!("An error occurs in parsing full name 1st part"))
-                .to_string(),
-        );
-        self.repo_name = Some(
-            iter.next()
-                .unwrap_or_else(|| die!("An error occurs in parsing full name 2nd part"))
-                .to_string(),
-        );
-        debug_assert!(iter.next().is_none(), "fullname has more than 2 parts");
-        debug!(
-            "set repo_name: {}, repo_owner: {}",
-            self.repo_name.as_ref().unwrap(),
-            self.repo_owner.as_ref().unwrap()
-        );
-        self
+        let (Some(owner), Some(name)) = (iter.next(), iter.next()) else {
+            bail!(MyError::InvalidFullName(full_name.to_string()));
+        };
+        let name = name.trim_end_matches(".git");
+        if owner.is_empty() || name.is_empty() {
+            bail!(MyError::InvalidFullName(full_name.to_string()));
+        }
+        debug!("set repo_name: {}, repo_owner: {}", name, owner);
+        self.repo_owner = Some(owner.to_string());
+        self.repo_name = Some(name.to_string());
+        Ok(self)
     }
     /// Set the `repo_name` and `repo_owner` by url.
     /// For example, with the url `https://github.com/lxl66566/bpm-rs/`, the `repo_owner` would be
     /// `lxl66566`, and the `repo_name` would be `bpm-rs`.
-    pub fn set_by_url(self, url: &str) -> Self {
-        let binding = Url::parse(url).expect("parsing invalid URL.");
-        let full_name = binding.path();
-        self.set_by_fullname(full_name)
-    }
-
-    fn search(&self) -> Result<Vec<String>> {
-        // Search API: https://docs.github.com/zh/rest/search/search?apiVersion=2022-11-28#search-repositories
-        let url = Url::parse_with_params(
-            self.api_base()
-                .join_all_str(["search", "repositories"])?
-                .as_str()
-                .trim_matches('/'),
-            &[
-                ("q", format!("{} in:name", self.name).as_str()),
-                ("page", "1"),
-            ],
-        )
-        .expect("This construct should be ok.");
+    pub fn set_by_url(self, url: &str) -> Result<Self> {
+        let binding = Url::parse(url).map_err(|_| MyError::InvalidFullName(url.to_string()))?;
+        self.set_by_fullname(binding.path())
+            .map_err(|_| MyError::InvalidFullName(url.to_string()).into())
+    }
+
+    fn search(&self, cli: &Cli, provider: &impl ReleaseProvider) -> Result<Vec<String>> {
+        let client = self.site.client();
+        let url = client.search_url(&self.name, cli.sort, cli.search_order())?;
         info!("search url: {}", &url);
-        let response = REQUEST_CLIENT.get(url).send();
-        match response {
-            Ok(r) if r.status().is_success() => {
-                let data: serde_json::Value = r.json().unwrap();
-                data["items"].as_array().map_or_else(
-                    || {
-                        die!("No items found in the response");
-                    },
-                    |items| {
-                        let repos: Vec<String> = items
-                            .iter()
-                            .map(|item| item["html_url"].as_str().unwrap_or_default().to_string())
-                            .collect();
-                        Ok(repos)
-                    },
-                )
-            }
-            Ok(r) => {
-                die!("Unexpected status: {}", r.status());
-            }
-            Err(e) => {
-                die!("Error fetching data: {}", e);
-            }
-        }
+        let data = provider.get_json(&*client, url)?;
+        client.search_results(&data)
     }
 
+    /// Search the name and let the user select a repo among the results, or
+    /// take the first one if not interactive or with `--select-first`.
     #[allow(clippy::significant_drop_tightening)]
-    pub fn ask(self, quiet: bool) -> Self {
+    pub fn ask(self, cli: &Cli, provider: &impl ReleaseProvider) -> Result<Self> {
         use terminal_menu::{button, label, menu, mut_menu, run};
-        let items = self.search().die("An error occurs in searching repos.");
-        assert!(!items.is_empty(), "No repos found.");
-        if quiet {
+        let items = self
+            .search(cli, provider)
+            .context("An error occurs in searching repos.")?;
+        if items.is_empty() {
+            bail!(MyError::NoRepoFound(self.name));
+        }
+        if !cli.is_interactive() || cli.select_first {
             return self.set_by_url(items[0].as_str());
         }
-        let mut menu_items = vec![label(
-            "Please select the repo you want to install:"
-                .bold()
-                .to_string(),
-        )];
-        menu_items.reserve(items.len());
-        items
-            .into_iter()
-            .map(button)
-            .for_each(|x| menu_items.push(x));
-        let select_menu = menu(menu_items);
-        run(&select_menu);
-        let temp = mut_menu(&select_menu);
-        let selected = temp.selected_item_name();
-        info!("selected repo: {}", selected);
-        self.set_by_url(selected)
-    }
-
-    pub fn get_asset(&mut self) -> &mut Self {
-        assert!(self.repo_owner.is_some() && self.repo_name.is_some());
-        let api = self
-            .api_base()
-            .join_all_str([
-                "repos",
-                self.repo_owner.as_deref().unwrap(),
-                self.repo_name.as_deref().unwrap(),
-                "releases",
-                "latest",
-            ])
-            .expect("Invalid path.");
-        debug!("Get assets from API: {}", api);
-        match REQUEST_CLIENT.get(api).send() {
-            Ok(response) if response.status().is_success() => {
-                let releases: serde_json::Value = response
-                    .json()
-                    .die("Assets API response is not a valid json");
-
-                self.version = Some(
-                    releases["tag_name"]
-                        .as_str()
-                        .unwrap_or_default()
-                        .to_string(),
-                );
+        let show_more = format!("... show {} more", items.len().saturating_sub(cli.results));
+        let mut shown = cli.results.min(items.len());
+        loop {
+            let mut menu_items = vec![label(
+                "Please select the repo you want to install:"
+                    .bold()
+                    .to_string(),
+            )];
+            menu_items.reserve(shown + 1);
+            items[..shown]
+                .iter()
+                .map(button)
+                .for_each(|x| menu_items.push(x));
+            if shown < items.len() {
+                menu_items.push(button(&show_more));
+            }
+            let select_menu = menu(menu_items);
+            run(&select_menu);
+            let selected = mut_menu(&select_menu).selected_item_name().to_string();
+            if selected == show_more {
+                shown = items.len();
+                continue;
+            }
+            info!("selected repo: {}", selected);
+            return self.set_by_url(&selected);
+        }
+    }
 
-                let raw_assets = releases["assets"]
-                    .as_array()
-                    .die("Assets API response has no array named `assets`");
-                if raw_assets.is_empty() {
-                    die!(
-                        "No releases found for {}/{}",
-                        self.repo_owner.as_ref().unwrap(),
-                        self.repo_name.as_ref().unwrap()
-                    );
-                }
+    /// Drop the assets obviously for the platforms other than `os`, see
+    /// [`is_foreign_asset`], so large releases are shorter to list and rank.
+    /// All assets are kept if none would be left.
+    fn prefilter_assets(&self, assets: Vec<Asset>, os: &str) -> Vec<Asset> {
+        let kept: Vec<_> = assets
+            .iter()
+            .filter(|asset| {
+                let name = map_tokens(&asset.name, &self.token_map);
+                !is_foreign_asset(&name, os, self.match_case)
+            })
+            .cloned()
+            .collect();
+        if kept.is_empty() {
+            assets
+        } else {
+            debug!("prefilter kept {} of {} assets", kept.len(), assets.len());
+            kept
+        }
+    }
 
-                let mut assets: Vec<String> = raw_assets
-                    .iter()
-                    .filter_map(|asset| asset["browser_download_url"].as_str().map(String::from))
-                    .collect();
-
-                fn not_empty_filter(
-                    vec: Vec<String>,
-                    filter: impl Fn(&String) -> bool,
-                ) -> Vec<String> {
-                    let temp: Vec<String> = vec.clone().into_iter().filter(filter).collect();
-                    if temp.is_empty() {
-                        vec
-                    } else {
-                        temp
-                    }
-                }
+    /// Rank the assets by how well they fit the current platform. The first
+    /// one is the asset to install.
+    ///
+    /// Assets obviously for other platforms are dropped unless `--all-assets`,
+    /// the rest are filtered by platform and architecture, then ordered by the
+    /// preferred formats and then [`format_rank`], then GNU before musl, then
+    /// the smaller size, and at last the file name, so the order doesn't
+    /// depend on the API's.
+    pub fn rank_assets(&self, assets: Vec<Asset>) -> Vec<Asset> {
+        self.rank_stages(assets).ranked
+    }
 
-                // Select platform
-                assets = not_empty_filter(assets, |asset| asset.to_lowercase().contains(OS));
+    /// Whether the asset name has any of the markers, after mapping its
+    /// tokens. Case-insensitive unless `--match-case`.
+    fn has_marker(&self, name: &str, markers: &[&str]) -> bool {
+        let name = map_tokens(name, &self.token_map);
+        markers
+            .iter()
+            .any(|m| contains_marker_with_case(&name, m, self.match_case))
+    }
 
-                #[cfg(windows)]
-                if !self.name.to_lowercase().contains("win") {
-                    assets.retain(|asset| asset.to_lowercase().contains("win"));
-                    assert!(!assets.is_empty(), "{}", MyError::NoAvailableAsset);
-                }
+    /// [`RepoHandler::rank_assets`] with the assets left after each stage.
+    pub fn rank_stages(&self, assets: Vec<Asset>) -> RankStages {
+        self.rank_stages_for(assets, OS, ARCH)
+    }
 
-                // Select architecture
-                assets = not_empty_filter(assets, |asset| asset.to_lowercase().contains(ARCH));
+    /// [`RepoHandler::rank_stages`] for the platform `os` and architecture
+    /// `arch`, as in [`std::env::consts`], instead of the current ones.
+    fn rank_stages_for(&self, assets: Vec<Asset>, os: &str, arch: &str) -> RankStages {
+        fn not_empty_filter(vec: Vec<Asset>, filter: impl Fn(&Asset) -> bool) -> Vec<Asset> {
+            let temp: Vec<Asset> = vec.clone().into_iter().filter(filter).collect();
+            if temp.is_empty() {
+                vec
+            } else {
+                temp
+            }
+        }
 
-                // Prefer GNU
-                if !self.prefer_gnu {
-                    assets.sort_by(|a, b| {
-                        a.to_lowercase()
-                            .contains("musl")
-                            .cmp(&b.to_lowercase().contains("musl"))
-                    });
-                }
+        let has_marker = |name: &str, markers: &[&str]| self.has_marker(name, markers);
+        let or_own = |markers: Vec<&'static str>, own| {
+            if markers.is_empty() {
+                vec![own]
+            } else {
+                markers
+            }
+        };
+        let platform = or_own(platform_markers_of(os), os);
+        let architecture = or_own(architecture_markers_of(arch), arch);
 
-                // Sort by archive type
-                assets.sort_by_key(|a| a.ends_with(".7z"));
-                // further sort by archive format
-                #[cfg(windows)]
-                {
-                    assets.sort_by_key(|a| a.contains(".tar."));
-                    assets.sort_by_key(|a| a.ends_with(".zip"));
-                }
-                #[cfg(not(windows))]
-                {
-                    assets.sort_by_key(|a| a.ends_with(".zip"));
-                    assets.sort_by_key(|a| a.contains(".tar."));
-                }
+        let input = assets.clone();
+        let assets = if self.all_assets {
+            assets
+        } else {
+            self.prefilter_assets(assets, os)
+        };
+        let prefilter = assets.clone();
+
+        // Select platform
+        #[allow(unused_mut)]
+        let mut assets = not_empty_filter(assets, |asset| has_marker(&asset.name, &platform));
 
-                if let Some(selected_asset) = assets.first() {
-                    self.asset = Some(selected_asset.to_string());
-                    eprintln!("Selected asset: {selected_asset}");
-                    self
+        // None left is reported as `NoAvailableAsset` by the caller
+        #[cfg(windows)]
+        if !has_marker(&self.name, &platform) {
+            assets.retain(|asset| has_marker(&asset.name, &platform));
+        }
+        let platform_assets = assets.clone();
+
+        // Select architecture, or universal binaries if none is for this
+        // architecture
+        let universal = universal_markers_of(os);
+        let markers = if assets
+            .iter()
+            .any(|asset| has_marker(&asset.name, &architecture))
+        {
+            &architecture
+        } else {
+            &universal
+        };
+        let mut assets = not_empty_filter(assets, |asset| has_marker(&asset.name, markers));
+        let architecture_assets = assets.clone();
+
+        // Drop builds against a newer glibc than the host's, which won't run,
+        // so musl builds are used if there's no compatible one
+        let glibc = self.glibc.or(*HOST_GLIBC);
+        if let Some(host) = glibc {
+            assets = not_empty_filter(assets, |asset| {
+                glibc_of_asset(&asset.name).is_none_or(|version| version <= host)
+            });
+        }
+
+        // Sort by the preferred formats and then the archive format, then prefer
+        // GNU and the newest glibc, and break ties by the smaller download and
+        // then the file name.
+        let is_musl = |a: &Asset| !self.prefer_gnu && a.name.to_lowercase().contains("musl");
+        let preferred_rank = |a: &Asset| {
+            let name = a.name.to_lowercase();
+            self.preferred_formats
+                .iter()
+                .position(|format| name.ends_with(&format!(".{}", format.to_lowercase())))
+                .unwrap_or(self.preferred_formats.len())
+        };
+        assets.sort_by(|a, b| {
+            preferred_rank(a)
+                .cmp(&preferred_rank(b))
+                .then_with(|| {
+                    format_rank(&a.name, &a.content_type)
+                        .cmp(&format_rank(&b.name, &b.content_type))
+                })
+                .then_with(|| is_musl(a).cmp(&is_musl(b)))
+                .then_with(|| glibc_of_asset(&b.name).cmp(&glibc_of_asset(&a.name)))
+                .then_with(|| a.size.cmp(&b.size))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        RankStages {
+            input,
+            prefilter,
+            platform: platform_assets,
+            architecture: architecture_assets,
+            ranked: assets,
+        }
+    }
+
+    /// Print how the assets of the fetched release are filtered and sorted by
+    /// [`RepoHandler::rank_assets`], to find out where an asset is dropped.
+    pub fn explain_assets(&self) {
+        let stages = self.rank_stages(self.assets.clone());
+        let names = |assets: &[Asset]| {
+            assets
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let print_filter = |stage: &str, markers: &[&str], before: &[Asset], after: &[Asset]| {
+            println!(
+                "{stage} filter (markers: {}): {} -> {}",
+                markers.join(", "),
+                before.len(),
+                after.len()
+            );
+            let dropped: Vec<_> = before
+                .iter()
+                .filter(|a| !after.contains(a))
+                .cloned()
+                .collect();
+            if !dropped.is_empty() {
+                println!("  dropped: {}", names(&dropped));
+            } else if !after.iter().any(|a| self.has_marker(&a.name, markers)) {
+                println!("  no asset matches, all kept");
+            }
+        };
+        println!("tokens:");
+        for asset in &stages.input {
+            let name = map_tokens(&asset.name, &self.token_map);
+            println!("  {}: {}", asset.name, tokenize(&name).join(" "));
+        }
+        if self.all_assets {
+            println!("prefilter: off by `--all-assets`");
+        } else {
+            println!(
+                "prefilter (assets for other platforms): {} -> {}",
+                stages.input.len(),
+                stages.prefilter.len()
+            );
+            let dropped: Vec<_> = stages
+                .input
+                .iter()
+                .filter(|a| !stages.prefilter.contains(a))
+                .cloned()
+                .collect();
+            if !dropped.is_empty() {
+                println!("  dropped: {}", names(&dropped));
+            }
+        }
+        print_filter(
+            "platform",
+            &platform_markers(),
+            &stages.prefilter,
+            &stages.platform,
+        );
+        print_filter(
+            "architecture",
+            &[architecture_markers(), universal_markers()].concat(),
+            &stages.platform,
+            &stages.architecture,
+        );
+        println!("sorted by preferred format, format, musl, glibc, size and name:");
+        for (i, asset) in stages.ranked.iter().enumerate() {
+            println!("  {:>2}. {}", i + 1, asset.name);
+        }
+    }
+
+    /// Print the assets of the fetched release with their sizes and ranks,
+    /// leaving out those dropped by [`RepoHandler::prefilter_assets`]. The
+    /// selected asset is marked with `*`, and assets filtered out by
+    /// [`RepoHandler::rank_assets`] have no rank.
+    pub fn print_assets(&self) {
+        if let Some(published_at) = &self.published_at {
+            println!("published at {published_at}");
+        }
+        let stages = self.rank_stages(self.assets.clone());
+        let ranked = stages.ranked;
+        for asset in &stages.prefilter {
+            let mark = if self.asset.as_deref() == Some(asset.url.as_str()) {
+                "*"
+            } else {
+                " "
+            };
+            let rank = ranked
+                .iter()
+                .position(|a| a == asset)
+                .map_or_else(|| "-".to_string(), |i| (i + 1).to_string());
+            println!(
+                "{mark} {rank:>4}  {:60}{:>12}",
+                asset.name,
+                fmt_size(asset.size)
+            );
+        }
+    }
+
+    pub fn get_asset(&mut self) -> Result<&mut Self> {
+        self.get_asset_from(&HttpProvider::default())?;
+        if !CLI.is_silent() {
+            eprintln!(
+                "Selected asset: {}",
+                self.asset.as_deref().unwrap_or_default()
+            );
+        }
+        Ok(self)
+    }
+
+    /// Fetch the release from `provider` and select the asset to install.
+    pub fn get_asset_from(&mut self, provider: &impl ReleaseProvider) -> Result<&mut Self> {
+        assert!(self.repo_owner.is_some() && self.repo_name.is_some());
+        let (owner, repo) = self.owner_and_repo();
+        let downloads = self.site.client().downloads(provider, owner, repo)?;
+        let release = if let Some(assets) = downloads {
+            if self.release != ReleaseSpec::Latest {
+                bail!(
+                    "{} has no releases, so `@{}` can't be used",
+                    self.full_name(),
+                    self.release
+                );
+            }
+            ReleaseInfo {
+                assets,
+                ..Default::default()
+            }
+        } else {
+            self.release_info(provider)?
+        };
+        self.version = release.tag;
+        self.release_name = release.name;
+        self.published_at = release.published_at;
+        self.assets = dedup_assets(release.assets);
+
+        let Some(selected_asset) = self.rank_assets(self.assets.clone()).into_iter().next() else {
+            bail!(MyError::NoAvailableAsset);
+        };
+        self.asset = Some(selected_asset.url);
+        self.asset_name = Some(selected_asset.name);
+        self.asset_id = Some(selected_asset.id);
+        self.asset_updated_at = Some(selected_asset.updated_at);
+        Ok(self)
+    }
+
+    /// The release given by [`ReleaseSpec`]. With a tag glob, the latest
+    /// release is the most recently published one matching it, since the
+    /// site's latest may not.
+    fn release_info(&mut self, provider: &impl ReleaseProvider) -> Result<ReleaseInfo> {
+        let releases = match &self.release {
+            ReleaseSpec::Latest => {
+                let latest = if self.newest || self.tag_glob.is_some() {
+                    newest_published_release(self.releases(provider)?, false)
+                } else {
+                    self.get_release(provider, &["latest"])?
+                };
+                if self.prefer_newest {
+                    newer_release(latest, self.newest_prerelease(provider)?)
                 } else {
-                    die!("{}", MyError::NoAvailableAsset);
+                    latest
                 }
             }
-            Ok(response) => {
-                die!(
-                    "Unexpected response status: {} from the releases API",
-                    response.status()
-                );
+            ReleaseSpec::Stable => newest_published_release(self.releases(provider)?, false),
+            ReleaseSpec::Pre => newest_published_release(self.releases(provider)?, true),
+            ReleaseSpec::Tag(tag) => self.get_release(provider, &["tags", tag])?,
+            ReleaseSpec::Range(req) => highest_matching_release(self.releases(provider)?, req),
+        };
+        let Some(releases) = releases else {
+            self.ensure_repo_exists(provider)?;
+            if self.release == ReleaseSpec::Latest && self.tag_glob.is_none() {
+                bail!(MyError::NoRelease(self.full_name()));
             }
+            bail!(MyError::NoMatchingRelease {
+                repo: self.full_name(),
+                spec: self.release.to_string(),
+                tag_glob: self.tag_glob.as_ref().map(ToString::to_string),
+            });
+        };
+
+        if let Some(html_url) = releases["html_url"].as_str() {
+            self.set_canonical_case(html_url);
+        }
+        let tag = releases["tag_name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let name = releases["name"]
+            .as_str()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string);
+
+        let raw_assets = self.site.client().release_assets(&releases)?;
+        if raw_assets.is_empty() {
+            bail!(MyError::NoReleaseAsset {
+                repo: self.full_name(),
+                tag,
+                hint: self.build_hint(provider).unwrap_or_default(),
+            });
+        }
+        Ok(ReleaseInfo {
+            tag: Some(tag),
+            name,
+            published_at: releases["published_at"].as_str().map(ToString::to_string),
+            assets: raw_assets,
+        })
+    }
+
+    /// Take the case of owner and repo name from `html_url` given by the API,
+    /// like `https://github.com/BurntSushi/ripgrep/releases/tag/14.1.0`, since
+    /// sites match them case-insensitively and the input may be in any case.
+    /// The name follows if it's the repo name.
+    fn set_canonical_case(&mut self, html_url: &str) {
+        let Ok(url) = Url::parse(html_url) else {
+            return;
+        };
+        let mut segments = url.path().trim_matches('/').split('/');
+        let (Some(owner), Some(repo)) = (segments.next(), segments.next()) else {
+            return;
+        };
+        let same =
+            |old: Option<&str>, new: &str| old.is_some_and(|old| old.eq_ignore_ascii_case(new));
+        if !same(self.repo_owner.as_deref(), owner) || !same(self.repo_name.as_deref(), repo) {
+            return;
+        }
+        if same(self.repo_name.as_deref(), &self.name) {
+            self.name = repo.to_string();
+        }
+        self.repo_owner = Some(owner.to_string());
+        self.repo_name = Some(repo.to_string());
+    }
+
+    /// The newest prerelease of this repo, if any.
+    fn newest_prerelease(
+        &self,
+        provider: &impl ReleaseProvider,
+    ) -> Result<Option<serde_json::Value>> {
+        Ok(self.releases(provider)?.into_iter().find(|release| {
+            release["prerelease"].as_bool() == Some(true)
+                && release["draft"].as_bool() != Some(true)
+        }))
+    }
+
+    /// GET the release of this repo at `path` of the releases API, like
+    /// `["latest"]`, or `None` if there's no such release.
+    fn get_release(
+        &self,
+        provider: &impl ReleaseProvider,
+        path: &[&str],
+    ) -> Result<Option<serde_json::Value>> {
+        let client = self.site.client();
+        let (owner, repo) = self.owner_and_repo();
+        let url = client.releases_url(owner, repo, path)?;
+        debug!("Get assets from API: {}", url);
+        match provider.get_json(&*client, url) {
+            Ok(release) => Ok(Some(release)),
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err.context("Failed to get the release")),
+        }
+    }
+
+    /// The recent releases of this repo, newest first. Only those matching the
+    /// tag glob are kept if it's set.
+    fn releases(&self, provider: &impl ReleaseProvider) -> Result<Vec<serde_json::Value>> {
+        let client = self.site.client();
+        let (owner, repo) = self.owner_and_repo();
+        let api = client.releases_url(owner, repo, &[])?;
+        debug!("Get releases from API: {}", api);
+        let data = match provider.get_json(&*client, api) {
+            Ok(data) => data,
             Err(err) => {
-                die!("Error fetching releases data: {}", err);
+                if is_not_found(&err) {
+                    self.ensure_repo_exists(provider)?;
+                }
+                return Err(err.context("Failed to get the releases"));
             }
+        };
+        let serde_json::Value::Array(releases) = data else {
+            bail!("Releases API response is not an array");
+        };
+        Ok(match &self.tag_glob {
+            Some(glob) => filter_by_tag_glob(releases, glob),
+            None => releases,
+        })
+    }
+
+    /// Fail with [`MyError::RepoNotFound`] if the repo API says the repo
+    /// doesn't exist. The releases API gives `404 Not Found` both for a
+    /// missing release and a missing repo, so this tells a typo from a repo
+    /// without releases. Other failures of the check are ignored.
+    fn ensure_repo_exists(&self, provider: &impl ReleaseProvider) -> Result<()> {
+        let client = self.site.client();
+        let (owner, repo) = self.owner_and_repo();
+        match provider.get_json(&*client, client.repo_url(owner, repo, &[])?) {
+            Err(err) if is_not_found(&err) => bail!(MyError::RepoNotFound(self.full_name())),
+            _ => Ok(()),
+        }
+    }
+
+    /// The version shown to users: the release title, or the tag if the
+    /// release has no title.
+    pub fn display_version(&self) -> &str {
+        self.release_name
+            .as_deref()
+            .or(self.version.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The owner and the name of this repo, empty if unknown.
+    fn owner_and_repo(&self) -> (&str, &str) {
+        (
+            self.repo_owner.as_deref().unwrap_or_default(),
+            self.repo_name.as_deref().unwrap_or_default(),
+        )
+    }
+
+    /// What tells this package apart from other inputs: the site, and the full
+    /// name or the name to search in lowercase, followed by the release.
+    pub fn package_key(&self) -> (Site, String) {
+        let name = if self.repo_owner.is_some() {
+            self.full_name()
+        } else {
+            self.name.clone()
+        };
+        (
+            self.site.clone(),
+            format!("{}@{}", name.to_lowercase(), self.release),
+        )
+    }
+
+    /// `owner/repo` of this repo.
+    fn full_name(&self) -> String {
+        format!(
+            "{}/{}",
+            self.repo_owner.as_deref().unwrap_or_default(),
+            self.repo_name.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// A hint on building this repo from source, if it has a build system we
+    /// recognize at its root.
+    fn build_hint(&mut self, provider: &impl ReleaseProvider) -> Option<String> {
+        let branch = self.default_branch(provider)?.to_string();
+        let client = self.site.client();
+        let (owner, repo) = self.owner_and_repo();
+        let mut api = client
+            .repo_url(owner, repo, &["contents", "Cargo.toml"])
+            .ok()?;
+        api.query_pairs_mut().append_pair("ref", &branch);
+        provider.get_json(&*client, api).ok()?;
+        Some(format!(
+            " It's a Rust project, you may build it with `cargo install --git {} --branch {branch}`.",
+            self.url()?
+        ))
+    }
+
+    /// The default branch of this repo, fetched from the repo API of GitHub or
+    /// Gitea once and kept on the handler. Branches should come from here
+    /// rather than be guessed as `main` or `master`.
+    fn default_branch(&mut self, provider: &impl ReleaseProvider) -> Option<&str> {
+        if self.default_branch.is_none() {
+            let client = self.site.client();
+            let (owner, repo) = self.owner_and_repo();
+            let repo = provider
+                .get_json(&*client, client.repo_url(owner, repo, &[]).ok()?)
+                .ok()?;
+            self.default_branch = Some(repo["default_branch"].as_str()?.to_string());
+        }
+        self.default_branch.as_deref()
+    }
+
+    /// Get the install plan of this repo. Should be called after
+    /// [`RepoHandler::get_asset`].
+    pub fn plan(&self) -> Plan<'_> {
+        Plan {
+            repo: self.full_name(),
+            url: self.url().map(|url| url.to_string()),
+            version: self.version.as_deref(),
+            release_name: self.release_name.as_deref(),
+            published_at: self.published_at.as_deref(),
+            asset: self.asset.as_deref(),
+            asset_name: self.asset_name.as_deref(),
+            files: Some(self.installed_files.as_slice()).filter(|files| !files.is_empty()),
         }
     }
 
     ///  update assets list. Returns `None` if has no update, `(old_version,
     /// new_version)` if has update.
-    pub fn update_asset(&mut self) -> Option<(String, String)> {
-        let old_version = self.version.clone().unwrap();
-        self.get_asset();
-        self.version.clone().and_then(|new_version| {
-            if old_version == new_version {
-                None
-            } else {
-                Some((old_version, new_version))
+    pub fn update_asset(&mut self) -> Result<Option<(String, String)>> {
+        self.update_asset_from(&HttpProvider::default())
+    }
+
+    /// [`RepoHandler::update_asset`] from `provider`. The release is requested
+    /// again even if it was fetched earlier in this process, or a new version
+    /// would never be seen. Versions of a rolling tag come with the update
+    /// time of their asset.
+    pub fn update_asset_from(
+        &mut self,
+        provider: &(impl ReleaseProvider + Clone),
+    ) -> Result<Option<(String, String)>> {
+        let old = self.clone();
+        self.get_asset_from(&provider.fresh())?;
+        if !self.is_update_of(&old) {
+            return Ok(None);
+        }
+        let version = |repo: &Self| {
+            let tag = repo.version.clone().unwrap_or_default();
+            match &repo.asset_updated_at {
+                Some(updated_at) if repo.is_rolling() => format!("{tag} ({updated_at})"),
+                _ => tag,
+            }
+        };
+        Ok(Some((version(&old), version(self))))
+    }
+
+    /// Whether the release tag is republished for every build, like
+    /// `nightly`, so the tag alone never changes on an update.
+    fn is_rolling(&self) -> bool {
+        const ROLLING_TAGS: [&str; 6] =
+            ["nightly", "latest", "continuous", "rolling", "edge", "dev"];
+        self.version
+            .as_deref()
+            .is_some_and(|tag| ROLLING_TAGS.contains(&tag.to_lowercase().as_str()))
+    }
+
+    /// Whether the fetched release of this handler is an update of `old`. The
+    /// same rolling tag is an update if its asset is newer, or a different
+    /// asset; other tags are compared by [`is_newer_version`].
+    fn is_update_of(&self, old: &Self) -> bool {
+        let (Some(old_version), Some(new_version)) = (&old.version, &self.version) else {
+            return false;
+        };
+        if old_version == new_version && self.is_rolling() {
+            return match (&old.asset_updated_at, &self.asset_updated_at) {
+                (Some(old_time), Some(new_time)) if old_time != new_time => new_time > old_time,
+                _ => old.asset_id != self.asset_id,
+            };
+        }
+        is_newer_version(old_version, new_version)
+    }
+}
+
+/// Whether the release tag `new` is an update of `old`. Semver tags are
+/// compared by precedence, so `v1.0.0` and `1.0.0` are the same version and a
+/// lower version is not an update. Other tags are compared by inequality.
+// Only `update_asset` uses it, which has no caller until installs land.
+#[allow(dead_code)]
+fn is_newer_version(old: &str, new: &str) -> bool {
+    match (parse_version(old), parse_version(new)) {
+        (Some(old_ver), Some(new_ver)) => match new_ver.cmp_precedence(&old_ver) {
+            Ordering::Greater => true,
+            Ordering::Equal => false,
+            Ordering::Less => {
+                warn!("The latest version `{new}` is lower than the installed `{old}`, skip it.");
+                false
             }
+        },
+        _ => old != new,
+    }
+}
+
+/// Installer packages that only install on one platform, by extension, with
+/// the platform as in [`std::env::consts::OS`].
+static PACKAGE_PLATFORMS: [(&str, &str); 5] = [
+    ("deb", "linux"),
+    ("rpm", "linux"),
+    ("msi", "windows"),
+    ("msix", "windows"),
+    ("dmg", "macos"),
+];
+
+/// Whether an asset is obviously not for the platform `os`: an installer
+/// package of another platform, or named with markers of other platforms
+/// only. Markers are matched case-sensitively with `match_case`, like
+/// `--match-case`, while extensions never are.
+fn is_foreign_asset(name: &str, os: &str, match_case: bool) -> bool {
+    let lower = name.to_lowercase();
+    if let Some((_, platform)) = PACKAGE_PLATFORMS
+        .iter()
+        .find(|(ext, _)| lower.ends_with(&format!(".{ext}")))
+    {
+        return *platform != os;
+    }
+    let has_any = |markers: Vec<&str>| {
+        markers
+            .iter()
+            .any(|m| contains_marker_with_case(name, m, match_case))
+    };
+    !has_any(platform_markers_of(os)) && has_any(other_platform_markers_of(os))
+}
+
+/// The release in a repo path copied from a release page, like
+/// `/owner/repo/releases/tag/v1.0` or `/owner/repo/releases/latest`.
+fn release_of_path(path: &str) -> Option<ReleaseSpec> {
+    let parts: Vec<_> = path.trim_matches('/').split('/').skip(2).collect();
+    match parts.as_slice() {
+        ["releases", "tag", tag @ ..] if !tag.is_empty() => Some(ReleaseSpec::Tag(tag.join("/"))),
+        ["releases", "latest"] => Some(ReleaseSpec::Latest),
+        _ => None,
+    }
+}
+
+/// The most recently published release that is not a draft, and not a
+/// prerelease unless `include_pre`. The site's `latest` is the one marked by
+/// the maintainer, which may be older.
+fn newest_published_release(
+    releases: Vec<serde_json::Value>,
+    include_pre: bool,
+) -> Option<serde_json::Value> {
+    releases
+        .into_iter()
+        .filter(|release| {
+            release["draft"].as_bool() != Some(true)
+                && (include_pre || release["prerelease"].as_bool() != Some(true))
+        })
+        .filter_map(|release| {
+            let published = DateTime::parse_from_rfc3339(release["published_at"].as_str()?).ok()?;
+            Some((published, release))
         })
+        .max_by_key(|(published, _)| *published)
+        .map(|(_, release)| release)
+}
+
+/// The non-draft release with the highest semver tag matching `req`. Tags
+/// that are not semver are skipped with a warning.
+fn highest_matching_release(
+    releases: Vec<serde_json::Value>,
+    req: &VersionReq,
+) -> Option<serde_json::Value> {
+    let mut skipped = Vec::new();
+    let best = releases
+        .into_iter()
+        .filter(|release| release["draft"].as_bool() != Some(true))
+        .filter_map(|release| {
+            let tag = release["tag_name"].as_str().unwrap_or_default();
+            let Some(version) = parse_version(tag) else {
+                skipped.push(tag.to_string());
+                return None;
+            };
+            Some((version, release))
+        })
+        .filter(|(version, _)| req.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp_precedence(b))
+        .map(|(_, release)| release);
+    if !skipped.is_empty() {
+        warn!(
+            "Tags that are not semver are ignored when matching `{req}`: {}",
+            skipped.join(", ")
+        );
+    }
+    best
+}
+
+/// The releases with tags matching `glob`.
+fn filter_by_tag_glob(releases: Vec<serde_json::Value>, glob: &Pattern) -> Vec<serde_json::Value> {
+    releases
+        .into_iter()
+        .filter(|release| glob.matches(release["tag_name"].as_str().unwrap_or_default()))
+        .collect()
+}
+
+/// Whether the error is a `404 Not Found` response.
+fn is_not_found(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref(),
+        Some(MyError::UnexpectedStatus { status, .. }) if *status == StatusCode::NOT_FOUND
+    )
+}
+
+/// Pick the release for `--prefer-newest`: the prerelease if its tag is newer
+/// than the stable one by semver precedence, otherwise the stable one. Falls
+/// back to whichever exists.
+fn newer_release(
+    stable: Option<serde_json::Value>,
+    pre: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    match (stable, pre) {
+        (Some(stable), Some(pre)) => {
+            let version = |release: &serde_json::Value| {
+                parse_version(release["tag_name"].as_str().unwrap_or_default())
+            };
+            match (version(&stable), version(&pre)) {
+                (Some(stable_ver), Some(pre_ver))
+                    if pre_ver.cmp_precedence(&stable_ver) == Ordering::Greater =>
+                {
+                    Some(pre)
+                }
+                _ => Some(stable),
+            }
+        }
+        (stable, pre) => stable.or(pre),
     }
 }
 
@@ -355,8 +1552,9 @@ impl fmt::Display for RepoHandler {
             "{}",
             fmt_repo_list(
                 self.name.as_str(),
-                self.url().as_str(),
-                self.version.as_deref().unwrap_or_default()
+                self.url()
+                    .map_or_else(|| "<local>".to_string(), |url| url.to_string()),
+                self.display_version()
             )
         )
     }
@@ -385,12 +1583,920 @@ impl PartialOrd for RepoHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assert2::assert;
+    use clap::Parser;
+    use reqwest::header::{HeaderValue, ACCEPT};
+    use std::env::consts::{ARCH, OS};
+
+    /// Canned responses keyed by url. Other urls are `404 Not Found`.
+    #[derive(Clone)]
+    struct MockProvider(HashMap<&'static str, serde_json::Value>);
+
+    impl ReleaseProvider for MockProvider {
+        fn get_json(&self, _forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value> {
+            self.0.get(url.as_str()).cloned().ok_or_else(|| {
+                MyError::UnexpectedStatus {
+                    status: StatusCode::NOT_FOUND,
+                    url,
+                }
+                .into()
+            })
+        }
+    }
+
+    /// An asset named `name`, with a url derived from it.
+    fn asset(name: &str) -> Asset {
+        Asset {
+            name: name.to_string(),
+            url: format!("https://example.com/{name}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_asset_from() {
+        let release = |tag: &str, prerelease: bool, published_at: &str| {
+            serde_json::json!({
+                "tag_name": tag,
+                "prerelease": prerelease,
+                "published_at": published_at,
+                "assets": [
+                    { "name": format!("tool-{tag}-{ARCH}-{OS}.tar.gz"), "browser_download_url": format!("https://example.com/{tag}/{OS}"), "size": 1 },
+                    { "name": format!("tool-{tag}-unknown.tar.gz"), "browser_download_url": format!("https://example.com/{tag}/unknown"), "size": 1 },
+                ],
+            })
+        };
+        let provider = MockProvider(HashMap::from([
+            (
+                "https://api.github.com/repos/owner/tool",
+                serde_json::json!({ "default_branch": "main" }),
+            ),
+            (
+                "https://api.github.com/repos/owner/tool/releases/latest",
+                release("v1.2.0", false, "2024-02-01T00:00:00Z"),
+            ),
+            (
+                "https://api.github.com/repos/owner/tool/releases?per_page=100",
+                serde_json::json!([
+                    release("v2.0.0", true, "2024-03-01T00:00:00Z"),
+                    release("v1.2.0", false, "2024-02-01T00:00:00Z"),
+                    release("v1.0.0", false, "2024-01-01T00:00:00Z"),
+                ]),
+            ),
+        ]));
+        let resolve = |input: &str| {
+            let mut repo = RepoHandler::parse_input(input, &Site::Github)?;
+            repo.get_asset_from(&provider)?;
+            anyhow::Ok((repo.version.unwrap(), repo.asset.unwrap()))
+        };
+        assert!(
+            resolve("owner/tool").unwrap()
+                == ("v1.2.0".into(), format!("https://example.com/v1.2.0/{OS}"))
+        );
+        assert!(resolve("owner/tool@^1.0").unwrap().0 == "v1.2.0");
+        let mut repo = RepoHandler::parse_input("owner/tool", &Site::Github).unwrap();
+        repo.get_asset_from(&provider).unwrap();
+        assert!(repo.plan().published_at == Some("2024-02-01T00:00:00Z"));
+        assert!(resolve("owner/tool@pre").unwrap().0 == "v2.0.0");
+        let mut repo = RepoHandler::parse_input("owner/tool", &Site::Github)
+            .unwrap()
+            .with_tag_glob(Some(&Pattern::new("v1.0.*").unwrap()));
+        repo.get_asset_from(&provider).unwrap();
+        assert!(repo.version.as_deref() == Some("v1.0.0"));
+        let err = resolve("owner/tool@v3.0.0").unwrap_err();
+        assert!(err.downcast_ref::<MyError>().map(MyError::kind) == Some("no_matching_release"));
+    }
+
+    #[test]
+    fn test_repo_not_found() {
+        let provider = MockProvider(HashMap::from([(
+            "https://api.github.com/repos/owner/tool",
+            serde_json::json!({ "default_branch": "main" }),
+        )]));
+        let kind = |input: &str| {
+            let mut repo = RepoHandler::parse_input(input, &Site::Github).unwrap();
+            let err = repo.get_asset_from(&provider).unwrap_err();
+            err.downcast_ref::<MyError>().map(MyError::kind)
+        };
+        // The repo exists, but has no release.
+        assert!(kind("owner/tool") == Some("no_release"));
+        assert!(kind("owner/tool@v1.0.0") == Some("no_matching_release"));
+        // A typo in the name.
+        assert!(kind("owner/tol") == Some("repo_not_found"));
+        assert!(kind("owner/tol@v1.0.0") == Some("repo_not_found"));
+        assert!(kind("owner/tol@stable") == Some("repo_not_found"));
+    }
+
+    #[test]
+    fn test_bitbucket_downloads() {
+        let provider = MockProvider(HashMap::from([(
+            "https://api.bitbucket.org/2.0/repositories/owner/tool/downloads?pagelen=100",
+            serde_json::json!({ "values": [
+                { "name": format!("tool-{ARCH}-{OS}.tar.gz"), "links": { "self": { "href": "https://example.com/tool" } }, "size": 1 },
+            ] }),
+        )]));
+        let mut repo = RepoHandler::parse_input("owner/tool", &Site::Bitbucket).unwrap();
+        repo.get_asset_from(&provider).unwrap();
+        assert!(repo.asset.as_deref() == Some("https://example.com/tool"));
+        let mut repo = RepoHandler::parse_input("owner/tool@v1.0.0", &Site::Bitbucket).unwrap();
+        assert!(repo.get_asset_from(&provider).is_err());
+        let mut repo = RepoHandler::parse_input("owner/other", &Site::Bitbucket).unwrap();
+        assert!(repo.get_asset_from(&provider).is_err());
+    }
+
+    #[test]
+    fn test_parse_input() {
+        // A name to search is left unresolved, without any request.
+        let repo = RepoHandler::parse_input("eza@stable", &Site::Github).unwrap();
+        assert!(repo.name == "eza");
+        assert!(repo.repo_owner.is_none() && repo.repo_name.is_none());
+        assert!(repo.release == ReleaseSpec::Stable);
+        let repo = RepoHandler::parse_input("eza-community/eza", &Site::Github).unwrap();
+        assert!(repo.full_name() == "eza-community/eza");
+        // A repo url on an unknown host is not resolved on `--source`.
+        let err =
+            RepoHandler::parse_input("https://codeberg.org/owner/tool", &Site::Github).unwrap_err();
+        assert!(err.downcast_ref::<MyError>().map(MyError::kind) == Some("unknown_host"));
+        let gitea = Site::Gitea {
+            host: "codeberg.org".into(),
+        };
+        let repo = RepoHandler::parse_input("https://codeberg.org/owner/tool", &gitea).unwrap();
+        assert!(repo.site == gitea && repo.full_name() == "owner/tool");
+    }
+
+    #[test]
+    fn test_search() {
+        let provider = MockProvider(HashMap::from([(
+            "https://api.github.com/search/repositories?q=eza+in%3Aname&page=1",
+            serde_json::json!({ "items": [
+                { "html_url": "https://github.com/eza-community/eza" },
+                { "html_url": "https://github.com/someone/eza" },
+            ] }),
+        )]));
+        let cli = Cli::try_parse_from(["bpm", "-q", "eza"]).unwrap();
+        let repo = RepoHandler::from_input("eza@stable", &cli, &provider).unwrap();
+        assert!(repo.full_name() == "eza-community/eza");
+        assert!(repo.release == ReleaseSpec::Stable);
+    }
+
+    #[test]
+    fn test_no_search() {
+        /// Fails the test on any request.
+        struct NoRequest;
+        impl ReleaseProvider for NoRequest {
+            fn get_json(&self, _forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value> {
+                panic!("unexpected request to {url}")
+            }
+        }
+        let cli = Cli::try_parse_from(["bpm", "--no-search", "eza"]).unwrap();
+        let err = RepoHandler::from_input("eza", &cli, &NoRequest).unwrap_err();
+        assert!(err.downcast_ref::<MyError>().map(MyError::kind) == Some("search_disabled"));
+        let repo = RepoHandler::from_input("eza-community/eza", &cli, &NoRequest).unwrap();
+        assert!(repo.full_name() == "eza-community/eza");
+    }
+
+    #[test]
+    fn test_canonical_case() {
+        let provider = MockProvider(HashMap::from([(
+            "https://api.github.com/repos/burntsushi/ripgrep/releases/latest",
+            serde_json::json!({
+                "tag_name": "14.1.0",
+                "html_url": "https://github.com/BurntSushi/ripgrep/releases/tag/14.1.0",
+                "assets": [{ "name": format!("rg-{ARCH}-{OS}.tar.gz"), "browser_download_url": "https://example.com/rg", "size": 1 }],
+            }),
+        )]));
+        let mut repo = RepoHandler::parse_input("burntsushi/ripgrep", &Site::Github).unwrap();
+        repo.get_asset_from(&provider).unwrap();
+        assert!(repo.full_name() == "BurntSushi/ripgrep");
+        assert!(repo.name == "ripgrep");
+        // A url of another repo, like a moved one, is not taken.
+        repo.set_canonical_case("https://github.com/someone/else/releases/tag/v1");
+        assert!(repo.full_name() == "BurntSushi/ripgrep");
+    }
+
+    #[test]
+    fn test_build_hint_default_branch() {
+        let provider = MockProvider(HashMap::from([
+            (
+                "https://api.github.com/repos/owner/tool/releases/latest",
+                serde_json::json!({ "tag_name": "v1.0.0", "assets": [] }),
+            ),
+            (
+                "https://api.github.com/repos/owner/tool",
+                serde_json::json!({ "default_branch": "develop" }),
+            ),
+            (
+                "https://api.github.com/repos/owner/tool/contents/Cargo.toml?ref=develop",
+                serde_json::json!({ "name": "Cargo.toml" }),
+            ),
+        ]));
+        let mut repo = RepoHandler::parse_input("owner/tool", &Site::Github).unwrap();
+        let err = repo.get_asset_from(&provider).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Release `v1.0.0` of owner/tool has no downloadable assets."));
+        assert!(err
+            .to_string()
+            .contains("cargo install --git https://github.com/owner/tool --branch develop"));
+        assert!(repo.default_branch.as_deref() == Some("develop"));
+    }
 
     #[test]
     fn test_set_by_url() {
-        let repo = RepoHandler::default().set_by_url("https://github.com/lxl66566/bpm-rs/");
-        assert_eq!(repo.url().as_str(), "https://github.com/lxl66566/bpm-rs");
+        let repo = RepoHandler::default()
+            .set_by_url("https://github.com/lxl66566/bpm-rs/")
+            .unwrap();
+        assert_eq!(
+            repo.url().unwrap().as_str(),
+            "https://github.com/lxl66566/bpm-rs"
+        );
         assert_eq!(repo.repo_name.unwrap(), "bpm-rs");
         assert_eq!(repo.repo_owner.unwrap(), "lxl66566");
     }
+
+    #[test]
+    fn test_invalid_full_name() {
+        for input in ["owner/", "/repo", "https://github.com/solo"] {
+            let err = RepoHandler::parse_input(input, &Site::Github).unwrap_err();
+            assert!(
+                err.downcast_ref::<MyError>().map(MyError::kind) == Some("invalid_full_name"),
+                "{input}: {err:#}"
+            );
+            assert!(err.to_string().contains(&format!("`{input}`")));
+        }
+    }
+
+    #[test]
+    fn test_url_with_subpath() {
+        for (url, release) in [
+            (
+                "https://github.com/lxl66566/bpm-rs/tree/main/src",
+                ReleaseSpec::Latest,
+            ),
+            (
+                "https://github.com/lxl66566/bpm-rs/blob/main/README.md",
+                ReleaseSpec::Latest,
+            ),
+            (
+                "https://github.com/lxl66566/bpm-rs/releases",
+                ReleaseSpec::Latest,
+            ),
+            (
+                "https://github.com/lxl66566/bpm-rs/releases/latest",
+                ReleaseSpec::Latest,
+            ),
+            (
+                "https://github.com/lxl66566/bpm-rs/releases/tag/v1.0",
+                ReleaseSpec::Tag("v1.0".into()),
+            ),
+        ] {
+            let repo = RepoHandler::parse_input(url, &Site::Github).unwrap();
+            assert_eq!(repo.repo_owner.as_deref(), Some("lxl66566"), "{url}");
+            assert_eq!(repo.repo_name.as_deref(), Some("bpm-rs"), "{url}");
+            assert!(repo.release == release, "{url}");
+        }
+        // An explicit `@` spec wins over the tag in the url.
+        let repo = RepoHandler::parse_input(
+            "https://github.com/a/b/releases/tag/v1.0@v2.0",
+            &Site::Github,
+        )
+        .unwrap();
+        assert!(repo.release == ReleaseSpec::Tag("v2.0".into()));
+    }
+
+    #[test]
+    fn test_display_without_url() {
+        let repo = RepoHandler::new("tool".into());
+        assert!(repo.url().is_none());
+        assert!(repo.to_string().contains("<local>"));
+        let repo = RepoHandler {
+            repo_name: Some("tool".into()),
+            ..RepoHandler::new("tool".into())
+        };
+        assert!(repo.url().is_none());
+    }
+
+    #[test]
+    fn test_bitbucket_url() {
+        let repo =
+            RepoHandler::parse_input("https://bitbucket.org/owner/tool/downloads/", &Site::Github)
+                .unwrap();
+        assert!(repo.site == Site::Bitbucket);
+        assert_eq!(
+            repo.url().unwrap().as_str(),
+            "https://bitbucket.org/owner/tool"
+        );
+        let repo =
+            RepoHandler::parse_input("https://github.com/owner/tool", &Site::Github).unwrap();
+        assert!(repo.site == Site::Github);
+    }
+
+    #[test]
+    fn test_source() {
+        let gitea = Site::Gitea {
+            host: "codeberg.org".into(),
+        };
+        let repo = RepoHandler::parse_input("owner/tool", &gitea).unwrap();
+        assert!(repo.site == gitea);
+        let repo = RepoHandler::parse_input("https://github.com/owner/tool", &gitea).unwrap();
+        assert!(repo.site == Site::Github);
+    }
+
+    #[test]
+    fn test_display_version() {
+        let mut repo = RepoHandler::new("tool".into());
+        repo.version = Some("20240101-abc123".into());
+        assert_eq!(repo.display_version(), "20240101-abc123");
+        repo.release_name = Some("Tool 1.2".into());
+        assert_eq!(repo.display_version(), "Tool 1.2");
+        assert!(repo.to_string().contains("Tool 1.2"));
+    }
+
+    #[test]
+    fn test_rank_assets() {
+        let assets: Vec<Asset> = [
+            format!("tool-{OS}-{ARCH}.7z"),
+            format!("tool-{OS}-{ARCH}-musl.7z"),
+            format!("tool-{OS}-{ARCH}"),
+            "tool-unknown-platform".to_string(),
+        ]
+        .iter()
+        .map(|name| asset(name))
+        .collect();
+        let ranked = RepoHandler::default().rank_assets(assets);
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].name, format!("tool-{OS}-{ARCH}"));
+        assert_eq!(ranked[2].name, format!("tool-{OS}-{ARCH}-musl.7z"));
+    }
+
+    #[test]
+    fn test_rank_assets_match_case() {
+        let assets = vec![
+            Asset {
+                size: 2,
+                ..asset(&format!("tool-{OS}-{ARCH}-full.tar.gz"))
+            },
+            Asset {
+                size: 1,
+                ..asset(&format!("tool-{}-{ARCH}.tar.gz", OS.to_uppercase()))
+            },
+        ];
+        let ranked = RepoHandler::default().rank_assets(assets.clone());
+        assert_eq!(ranked[0], assets[1]);
+        let ranked = RepoHandler::default()
+            .with_match_case(true)
+            .rank_assets(assets.clone());
+        assert_eq!(ranked, [assets[0].clone()]);
+    }
+
+    #[test]
+    fn test_rank_assets_glibc() {
+        let assets: Vec<Asset> = [
+            format!("tool-{OS}-{ARCH}-glibc2.17.tar.gz"),
+            format!("tool-{OS}-{ARCH}-glibc-2.31.tar.gz"),
+            format!("tool-{OS}-{ARCH}-glibc2.39.tar.gz"),
+            format!("tool-{OS}-{ARCH}-musl.tar.gz"),
+        ]
+        .iter()
+        .map(|name| asset(name))
+        .collect();
+        let rank = |glibc| {
+            let repo = RepoHandler {
+                glibc: Some(glibc),
+                ..Default::default()
+            };
+            repo.rank_assets(assets.clone())
+                .into_iter()
+                .map(|asset| asset.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rank((2, 35)),
+            [
+                assets[1].name.clone(),
+                assets[0].name.clone(),
+                assets[3].name.clone()
+            ]
+        );
+        // No compatible glibc build, so musl is used.
+        assert_eq!(rank((2, 12)), [assets[3].name.clone()]);
+    }
+
+    #[test]
+    fn test_glibc_version() {
+        assert_eq!(
+            parse_ldd_version("ldd (Debian GLIBC 2.36-9+deb12u4) 2.36\nCopyright"),
+            Some((2, 36))
+        );
+        assert_eq!(parse_ldd_version("musl libc (x86_64)\nVersion 1.2.4"), None);
+        assert_eq!(
+            glibc_of_asset("tool-x86_64-linux-glibc2.17.tar.gz"),
+            Some((2, 17))
+        );
+        assert_eq!(glibc_of_asset("tool_glibc-2.31_amd64.zip"), Some((2, 31)));
+        assert_eq!(glibc_of_asset("tool-x86_64-unknown-linux-gnu.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_rank_assets_universal() {
+        let assets = vec![
+            asset("tool-darwin-x64.tar.gz"),
+            asset("tool-darwin-universal.tar.gz"),
+            asset("tool-linux-arm64.tar.gz"),
+        ];
+        let rank = |os, arch| {
+            RepoHandler::default()
+                .rank_stages_for(assets.clone(), os, arch)
+                .ranked
+        };
+        // No asset for Apple silicon, so the universal binary is used.
+        assert_eq!(rank("macos", "aarch64"), [assets[1].clone()]);
+        assert_eq!(rank("macos", "x86_64"), [assets[0].clone()]);
+        assert_eq!(rank("linux", "aarch64"), [assets[2].clone()]);
+    }
+
+    #[test]
+    fn test_rank_assets_tiebreak() {
+        let sized = |ext: &str, size| Asset {
+            size,
+            ..asset(&format!("tool-{OS}-{ARCH}.{ext}"))
+        };
+        let ranked = RepoHandler::default().rank_assets(vec![
+            sized("tar.xz", 200),
+            sized("tar.zst", 100),
+            sized("tar.gz", 100),
+        ]);
+        let names: Vec<_> = ranked.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                format!("tool-{OS}-{ARCH}.tar.gz"),
+                format!("tool-{OS}-{ARCH}.tar.zst"),
+                format!("tool-{OS}-{ARCH}.tar.xz"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_map() {
+        let marker = architecture_markers()[0];
+        let assets = vec![
+            asset(&format!("tool-{OS}-mine")),
+            asset(&format!("tool-{OS}-other")),
+        ];
+        let ranked = RepoHandler::default()
+            .with_token_map(&[("mine".into(), marker.into())])
+            .rank_assets(assets);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].name, format!("tool-{OS}-mine"));
+    }
+
+    #[test]
+    fn test_preferred_formats() {
+        let assets: Vec<_> = ["zip", "tar.gz", "tar.xz"]
+            .iter()
+            .map(|ext| asset(&format!("tool-{OS}-{ARCH}.{ext}")))
+            .collect();
+        let first = |formats: &[&str]| {
+            let formats: Vec<String> = formats.iter().map(ToString::to_string).collect();
+            RepoHandler::default()
+                .with_preferred_formats(&formats)
+                .rank_assets(assets.clone())
+                .remove(0)
+                .name
+        };
+        assert_eq!(
+            first(&["tar.zst", "tar.xz", "tar.gz"]),
+            format!("tool-{OS}-{ARCH}.tar.xz")
+        );
+        assert_eq!(first(&["tar.zst", ".zip"]), format!("tool-{OS}-{ARCH}.zip"));
+        // None of the preferred formats is present, fall back to the default.
+        assert_eq!(first(&["tar.zst"]), first(&[]));
+    }
+
+    #[test]
+    fn test_dedup_assets() {
+        let uploaded = |name: &str, id, updated_at: &str| Asset {
+            id,
+            updated_at: updated_at.into(),
+            ..asset(name)
+        };
+        let assets = dedup_assets(vec![
+            uploaded("tool.zip", 1, "2024-01-01T00:00:00Z"),
+            uploaded("tool.tar.gz", 2, "2024-01-01T00:00:00Z"),
+            uploaded("tool.zip", 3, "2024-02-01T00:00:00Z"),
+            uploaded("tool.zip", 4, "2023-12-01T00:00:00Z"),
+        ]);
+        let ids: Vec<_> = assets.iter().map(|a| a.id).collect();
+        assert_eq!(ids, [3, 2]);
+    }
+
+    #[test]
+    fn test_is_foreign_asset() {
+        assert!(is_foreign_asset("tool_1.0_amd64.deb", "macos", false));
+        assert!(!is_foreign_asset("tool_1.0_amd64.deb", "linux", false));
+        assert!(is_foreign_asset(
+            "tool-x86_64-pc-windows-msvc.zip",
+            "linux",
+            false
+        ));
+        assert!(!is_foreign_asset(
+            "tool-x86_64-unknown-linux-gnu.tar.gz",
+            "linux",
+            false
+        ));
+        // Names without any platform could be for any.
+        assert!(!is_foreign_asset("tool.tar.gz", "linux", false));
+        assert!(!is_foreign_asset(
+            "tool-darwin-linux-universal.tar.gz",
+            "linux",
+            false
+        ));
+        // Markers in another case don't count with `--match-case`.
+        assert!(!is_foreign_asset(
+            "tool-Linux-windows.tar.gz",
+            "linux",
+            false
+        ));
+        assert!(is_foreign_asset("tool-Linux-windows.tar.gz", "linux", true));
+    }
+
+    #[test]
+    fn test_prefilter_stage() {
+        let assets = vec![
+            asset("tool_1.0_amd64.deb"),
+            asset("tool-x86_64-pc-windows-msvc.zip"),
+            asset("tool-x86_64-unknown-linux-gnu.tar.gz"),
+        ];
+        let repo = RepoHandler::new("tool".into());
+        let stages = repo.rank_stages_for(assets.clone(), "linux", "x86_64");
+        // The input keeps the dropped assets, so `--explain` can show them.
+        assert!(stages.input == assets);
+        assert!(stages.prefilter == [assets[0].clone(), assets[2].clone()]);
+        let stages = repo
+            .with_all_assets(true)
+            .rank_stages_for(assets.clone(), "linux", "x86_64");
+        assert!(stages.prefilter == assets);
+    }
+
+    #[test]
+    fn test_format_rank() {
+        let mut names = ["a.7z", "a.zip", "a", "a.tar", "a.tar.gz", "a.tar.xz"];
+        names.sort_by_key(|name| format_rank(name, ""));
+        #[cfg(not(windows))]
+        assert_eq!(
+            names,
+            ["a.tar", "a.tar.gz", "a.tar.xz", "a.zip", "a", "a.7z"]
+        );
+        #[cfg(windows)]
+        assert_eq!(
+            names,
+            ["a.zip", "a.tar", "a.tar.gz", "a.tar.xz", "a", "a.7z"]
+        );
+    }
+
+    #[test]
+    fn test_archive_format_content_type() {
+        for (name, content_type, format) in [
+            // A known extension wins over a generic content type.
+            ("tool.zip", "application/octet-stream", ArchiveFormat::Zip),
+            ("tool.tar.gz", "application/zip", ArchiveFormat::Tar),
+            // Otherwise the content type decides.
+            ("tool-linux", "application/gzip", ArchiveFormat::Tar),
+            ("tool-1.2", "application/zip", ArchiveFormat::Zip),
+            (
+                "tool-1.2",
+                "application/x-7z-compressed",
+                ArchiveFormat::SevenZip,
+            ),
+            (
+                "tool-linux",
+                "application/octet-stream",
+                ArchiveFormat::Other,
+            ),
+            ("tool-linux", "", ArchiveFormat::Other),
+        ] {
+            assert!(
+                ArchiveFormat::of(name, content_type) == format,
+                "{name} {content_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_trailing_git() {
+        let repo = RepoHandler::default()
+            .set_by_fullname("owner/repo.git")
+            .unwrap();
+        assert_eq!(repo.repo_name.unwrap(), "repo");
+        let repo =
+            RepoHandler::parse_input("https://github.com/lxl66566/bpm-rs.git", &Site::Github)
+                .unwrap();
+        assert_eq!(repo.name, "bpm-rs");
+        assert_eq!(
+            repo.url().unwrap().as_str(),
+            "https://github.com/lxl66566/bpm-rs"
+        );
+    }
+
+    #[test]
+    fn test_client_builder_ca_cert() {
+        // A self-signed CA made for this test only.
+        const CA_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBgzCCASmgAwIBAgIUALbtYmKvpPYcFmCO4BmtTUfEqE0wCgYIKoZIzj0EAwIw
+FjEUMBIGA1UEAwwLYnBtIHRlc3QgQ0EwIBcNMjYxMDE3MDIxMjA2WhgPMjEyNjA5
+MjMwMjEyMDZaMBYxFDASBgNVBAMMC2JwbSB0ZXN0IENBMFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEEih17M1tXUAT7OhGanN2MCb3fpkIdaV0T7xKNhgqVBtFRGLF
+W+BTv1iBo4LVH5J+jmLgVJx6J1hxYDGXg/+ufKNTMFEwHQYDVR0OBBYEFF08tbXh
+uPOdDNuXcY4Hn2iyTerLMB8GA1UdIwQYMBaAFF08tbXhuPOdDNuXcY4Hn2iyTerL
+MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIhAP/JXgP1+ugoIkql
+jXb0fCe9qh8pjhiC8u6QOxk7AYepAiArtijAAOa5mneA05gdIljFz/aJA4El/rEV
+E8UONDa/rQ==
+-----END CERTIFICATE-----
+";
+        let dir = std::env::temp_dir().join(format!("bpm-test-ca-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let valid = dir.join("ca.pem");
+        std::fs::write(&valid, CA_CERT).unwrap();
+        let invalid = dir.join("invalid.pem");
+        std::fs::write(&invalid, "not a certificate").unwrap();
+
+        let builder = client_builder(APP_USER_AGENT, Some(&valid), false).unwrap();
+        assert!(builder.build().is_ok());
+        let err = client_builder(APP_USER_AGENT, Some(&invalid), false).unwrap_err();
+        assert!(err.to_string().contains("not a valid PEM certificate"));
+        assert!(client_builder(APP_USER_AGENT, Some(&dir.join("missing.pem")), false).is_err());
+        assert!(client_builder(APP_USER_AGENT, None, true)
+            .unwrap()
+            .build()
+            .is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_redact_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        let redacted = redact_headers(&headers);
+        assert_eq!(
+            redacted,
+            vec!["accept: application/json", "authorization: <redacted>"]
+        );
+    }
+
+    #[test]
+    fn test_request_error() {
+        // Nothing listens on port 1, so the connection is refused.
+        let url = Url::parse("http://127.0.0.1:1/").unwrap();
+        let err = reqwest::blocking::get(url.clone()).unwrap_err();
+        let err = request_error(err, &url);
+        assert!(err.downcast_ref::<MyError>().map(MyError::kind) == Some("unreachable"));
+        assert!(err.to_string().starts_with("Can't connect to 127.0.0.1."));
+    }
+
+    #[test]
+    fn test_get_json_cached() {
+        let url = Url::parse("https://example.invalid/repos/a/b/releases/latest").unwrap();
+        let data = serde_json::json!({ "tag_name": "v1.0.0" });
+        RESPONSE_CACHE
+            .lock()
+            .unwrap()
+            .insert(url.clone(), data.clone());
+        // Served from the cache, so the unresolvable host is never requested.
+        assert_eq!(get_json(&*Site::Github.client(), url).unwrap(), data);
+    }
+
+    #[test]
+    fn test_update_asset_fresh() {
+        /// Serves `stale` like a cache would, and `current` when fresh.
+        #[derive(Clone)]
+        struct Cached {
+            stale: MockProvider,
+            current: MockProvider,
+            fresh: bool,
+        }
+        impl ReleaseProvider for Cached {
+            fn get_json(&self, forge: &dyn ForgeClient, url: Url) -> Result<serde_json::Value> {
+                if self.fresh {
+                    &self.current
+                } else {
+                    &self.stale
+                }
+                .get_json(forge, url)
+            }
+            fn fresh(&self) -> Self {
+                Self {
+                    fresh: true,
+                    ..self.clone()
+                }
+            }
+        }
+        let latest = |tag: &str| {
+            MockProvider(HashMap::from([(
+                "https://api.github.com/repos/owner/tool/releases/latest",
+                serde_json::json!({
+                    "tag_name": tag,
+                    "assets": [{ "name": format!("tool-{ARCH}-{OS}.tar.gz"), "browser_download_url": format!("https://example.com/{tag}"), "size": 1 }],
+                }),
+            )]))
+        };
+        let provider = Cached {
+            stale: latest("v1.0.0"),
+            current: latest("v1.1.0"),
+            fresh: false,
+        };
+        let mut repo = RepoHandler::parse_input("owner/tool", &Site::Github).unwrap();
+        repo.get_asset_from(&provider).unwrap();
+        assert!(
+            repo.update_asset_from(&provider).unwrap() == Some(("v1.0.0".into(), "v1.1.0".into()))
+        );
+    }
+
+    #[test]
+    fn test_rolling_update() {
+        let nightly = |id: u64, updated_at: &str| {
+            MockProvider(HashMap::from([(
+                "https://api.github.com/repos/owner/tool/releases/latest",
+                serde_json::json!({
+                    "tag_name": "nightly",
+                    "assets": [{ "name": format!("tool-{ARCH}-{OS}.tar.gz"), "browser_download_url": "https://example.com/nightly", "size": 1, "id": id, "updated_at": updated_at }],
+                }),
+            )]))
+        };
+        let update = |provider: &MockProvider| {
+            let mut repo = RepoHandler::parse_input("owner/tool", &Site::Github).unwrap();
+            repo.get_asset_from(&nightly(1, "2024-01-01T00:00:00Z"))
+                .unwrap();
+            repo.update_asset_from(provider).unwrap()
+        };
+        // The nightly tag is republished with a new asset.
+        assert!(
+            update(&nightly(2, "2024-01-02T00:00:00Z"))
+                == Some((
+                    "nightly (2024-01-01T00:00:00Z)".into(),
+                    "nightly (2024-01-02T00:00:00Z)".into()
+                ))
+        );
+        assert!(update(&nightly(1, "2024-01-01T00:00:00Z")).is_none());
+        // Same time but another asset, e.g. re-uploaded.
+        assert!(update(&nightly(3, "2024-01-01T00:00:00Z")).is_some());
+        // An older build is not an update.
+        assert!(update(&nightly(4, "2023-12-31T00:00:00Z")).is_none());
+    }
+
+    #[test]
+    fn test_update_asset_prefix() {
+        let latest = |tag: &str| {
+            MockProvider(HashMap::from([(
+                "https://api.github.com/repos/owner/tool/releases/latest",
+                serde_json::json!({
+                    "tag_name": tag,
+                    "assets": [{ "name": format!("tool-{ARCH}-{OS}.tar.gz"), "browser_download_url": format!("https://example.com/{tag}"), "size": 1 }],
+                }),
+            )]))
+        };
+        let update = |from: &str, to: &str| {
+            let mut repo = RepoHandler::parse_input("owner/tool", &Site::Github).unwrap();
+            repo.get_asset_from(&latest(from)).unwrap();
+            repo.update_asset_from(&latest(to)).unwrap()
+        };
+        // Dropping the `v` prefix is not an update, and neither is a downgrade.
+        assert!(update("v1.0.0", "1.0.0").is_none());
+        assert!(update("v1.0.0", "0.9.0").is_none());
+        assert!(update("v1.0.0", "1.0.1") == Some(("v1.0.0".into(), "1.0.1".into())));
+    }
+
+    #[test]
+    fn test_is_newer_version() {
+        assert!(is_newer_version("v1.0.0", "1.0.1"));
+        assert!(is_newer_version("1.0.0", "v1.1.0"));
+        assert!(!is_newer_version("v1.0.0", "1.0.0"));
+        assert!(!is_newer_version("V1.0.0", "v1.0.0"));
+        assert!(!is_newer_version("1.0.1", "v1.0.0"));
+        assert!(!is_newer_version("1.0.0+build.1", "1.0.0+build.2"));
+        assert!(is_newer_version("1.0.0-rc.1", "1.0.0"));
+        assert!(is_newer_version("nightly-2024-01-01", "nightly-2024-01-02"));
+        assert!(!is_newer_version("nightly", "nightly"));
+    }
+
+    #[test]
+    fn test_newer_release() {
+        let release = |tag: &str| serde_json::json!({ "tag_name": tag });
+        let pick = |stable: Option<&str>, pre: Option<&str>| {
+            newer_release(stable.map(release), pre.map(release))
+                .map(|r| r["tag_name"].as_str().unwrap().to_string())
+        };
+        assert!(pick(Some("v1.0.0"), Some("v1.1.0-rc1")).as_deref() == Some("v1.1.0-rc1"));
+        assert!(pick(Some("v1.1.0"), Some("v1.1.0-rc1")).as_deref() == Some("v1.1.0"));
+        assert!(pick(Some("v1.0.0"), Some("nightly")).as_deref() == Some("v1.0.0"));
+        assert!(pick(Some("v1.0.0"), None).as_deref() == Some("v1.0.0"));
+        assert!(pick(None, Some("v0.1.0-beta")).as_deref() == Some("v0.1.0-beta"));
+        assert!(pick(None, None).is_none());
+    }
+
+    #[test]
+    fn test_release_spec() {
+        let repo = RepoHandler::parse_input("lxl66566/bpm-rs@^1.2", &Site::Github).unwrap();
+        assert!(repo.repo_name.as_deref() == Some("bpm-rs"));
+        assert!(repo.release == ReleaseSpec::Range(VersionReq::parse("^1.2").unwrap()));
+        let repo =
+            RepoHandler::parse_input("https://github.com/lxl66566/bpm-rs@v0.1.0", &Site::Github)
+                .unwrap();
+        assert!(repo.release == ReleaseSpec::Tag("v0.1.0".into()));
+        assert!("1.2.3".parse::<ReleaseSpec>().unwrap() == ReleaseSpec::Tag("1.2.3".into()));
+        assert!("latest".parse::<ReleaseSpec>().unwrap() == ReleaseSpec::Latest);
+        assert!("Stable".parse::<ReleaseSpec>().unwrap() == ReleaseSpec::Stable);
+        assert!("pre".parse::<ReleaseSpec>().unwrap() == ReleaseSpec::Pre);
+        let repo = RepoHandler::parse_input("lxl66566/bpm-rs@pre", &Site::Github).unwrap();
+        assert!(repo.release == ReleaseSpec::Pre);
+        assert!("^foo".parse::<ReleaseSpec>().is_err());
+    }
+
+    #[test]
+    fn test_filter_by_tag_glob() {
+        let releases: Vec<_> = ["v1.2.0", "v1.2.0-nightly-20240101", "v1.1.0", "nightly"]
+            .iter()
+            .map(|tag| serde_json::json!({ "tag_name": tag }))
+            .collect();
+        let tags = |glob: &str| {
+            filter_by_tag_glob(releases.clone(), &Pattern::new(glob).unwrap())
+                .iter()
+                .map(|release| release["tag_name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tags("v*.*.*"),
+            ["v1.2.0", "v1.2.0-nightly-20240101", "v1.1.0"]
+        );
+        assert_eq!(tags("v*.*.[0-9]"), ["v1.2.0", "v1.1.0"]);
+        assert_eq!(tags("*-nightly-*"), ["v1.2.0-nightly-20240101"]);
+    }
+
+    #[test]
+    fn test_highest_matching_release() {
+        let release =
+            |tag: &str, draft: bool| serde_json::json!({ "tag_name": tag, "draft": draft });
+        let releases = vec![
+            release("v2.0.0", false),
+            release("v1.3.0", true),
+            release("nightly", false),
+            release("v1.2.5", false),
+            release("v1.3.0-rc.1", false),
+            release("1.2.0", false),
+        ];
+        let pick = |req: &str| {
+            highest_matching_release(releases.clone(), &VersionReq::parse(req).unwrap())
+                .map(|r| r["tag_name"].as_str().unwrap().to_string())
+        };
+        assert!(pick("^1.2").as_deref() == Some("v1.2.5"));
+        assert!(pick("~1.2.0").as_deref() == Some("v1.2.5"));
+        assert!(pick(">=2").as_deref() == Some("v2.0.0"));
+        assert!(pick("^3").is_none());
+    }
+
+    #[test]
+    fn test_newest_published_release() {
+        let release = |tag: &str, published: &str, draft: bool, prerelease: bool| {
+            serde_json::json!({
+                "tag_name": tag,
+                "published_at": published,
+                "draft": draft,
+                "prerelease": prerelease,
+            })
+        };
+        // `v1.0.0` is marked as latest, but `v1.1.0` is published later.
+        let releases = vec![
+            release("v1.2.0", "2024-03-01T00:00:00Z", true, false),
+            release("v1.2.0-rc.1", "2024-02-15T00:00:00Z", false, true),
+            release("v1.0.0", "2024-01-01T00:00:00Z", false, false),
+            release("v1.1.0", "2024-02-01T00:00:00Z", false, false),
+        ];
+        let newest = newest_published_release(releases.clone(), false).unwrap();
+        assert!(newest["tag_name"] == "v1.1.0");
+        let newest = newest_published_release(releases, true).unwrap();
+        assert!(newest["tag_name"] == "v1.2.0-rc.1");
+        assert!(newest_published_release(vec![], true).is_none());
+    }
+
+    #[test]
+    fn test_plan() {
+        let mut repo = RepoHandler::parse_input("lxl66566/bpm-rs", &Site::Github).unwrap();
+        repo.version = Some("v0.1.0".into());
+        repo.asset = Some("https://example.com/download?id=1".into());
+        repo.asset_name = Some("bpm.tar.gz".into());
+        let plan = serde_json::to_value(repo.plan()).unwrap();
+        assert_eq!(plan["repo"], "lxl66566/bpm-rs");
+        assert_eq!(plan["url"], "https://github.com/lxl66566/bpm-rs");
+        assert_eq!(plan["version"], "v0.1.0");
+        assert_eq!(plan["asset"], "https://example.com/download?id=1");
+        assert_eq!(plan["asset_name"], "bpm.tar.gz");
+        assert!(plan.get("files").is_none());
+        let report = serde_json::to_value(PlanReport::new(vec![repo.plan()])).unwrap();
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["plans"][0]["repo"], "lxl66566/bpm-rs");
+    }
 }
@@ -0,0 +1,218 @@
+use crate::utils::err::MyError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use url::{Host, Position, Url};
+
+/// The site hosting a repo. It's stored as a string like `github` or
+/// `gitea:git.example.com`, see its [`Display`](fmt::Display) and [`FromStr`]
+/// impls.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Site {
+    #[default]
+    Github,
+    GithubEnterprise {
+        host: String,
+    },
+    Gitea {
+        host: String,
+    },
+    /// Bitbucket Cloud, which has downloads instead of releases.
+    Bitbucket,
+}
+
+impl Site {
+    /// The base url of the site, where the repos are.
+    pub fn base(&self) -> Url {
+        let url = match self {
+            Self::Github => "https://github.com".to_string(),
+            Self::Bitbucket => "https://bitbucket.org".to_string(),
+            Self::GithubEnterprise { host } | Self::Gitea { host } => format!("https://{host}"),
+        };
+        Url::parse(&url).expect("site host should be a valid url host")
+    }
+
+    /// The base url of the REST API of the site. It ends with `/`, so joining
+    /// a path keeps the version segment like `v1`.
+    pub fn api_base(&self) -> Url {
+        let url = match self {
+            Self::Github => "https://api.github.com/".to_string(),
+            Self::GithubEnterprise { host } => format!("https://{host}/api/v3/"),
+            Self::Gitea { host } => format!("https://{host}/api/v1/"),
+            Self::Bitbucket => "https://api.bitbucket.org/2.0/".to_string(),
+        };
+        Url::parse(&url).expect("site host should be a valid url host")
+    }
+
+    /// The site of a repo url: the public site of its host, or `source` if the
+    /// url is on the host of `source`. Self-hosted sites can't be told by the
+    /// host, so a url on any other host is [`MyError::UnknownHost`].
+    pub fn of_url(url: &Url, source: &Self) -> Result<Self, MyError> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| MyError::InvalidFullName(url.to_string()))?;
+        if let Some(site) = Self::from_host(host) {
+            return Ok(site);
+        }
+        let base = source.base();
+        if base.host_str() == Some(host)
+            && base.port_or_known_default() == url.port_or_known_default()
+        {
+            return Ok(source.clone());
+        }
+        Err(MyError::UnknownHost(
+            url[Position::BeforeHost..Position::AfterPort].to_string(),
+        ))
+    }
+
+    /// The public site of a url host, like `github.com`. Self-hosted sites
+    /// can't be told by the host, so they are `None`.
+    pub fn from_host(host: &str) -> Option<Self> {
+        match host.to_lowercase().trim_start_matches("www.") {
+            "github.com" => Some(Self::Github),
+            "bitbucket.org" => Some(Self::Bitbucket),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Github => write!(f, "github"),
+            Self::GithubEnterprise { host } => write!(f, "github-enterprise:{host}"),
+            Self::Gitea { host } => write!(f, "gitea:{host}"),
+            Self::Bitbucket => write!(f, "bitbucket"),
+        }
+    }
+}
+
+/// Whether `host` is a url host, optionally followed by a port, and nothing
+/// else, so it can't change the path or query of the urls built on it.
+fn is_valid_host(host: &str) -> bool {
+    if host.contains(['/', '?', '#']) {
+        return false;
+    }
+    let name = host
+        .rsplit_once(':')
+        .filter(|(_, port)| port.parse::<u16>().is_ok())
+        .map_or(host, |(name, _)| name);
+    Host::parse(name).is_ok()
+}
+
+impl FromStr for Site {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, host) = s.split_once(':').unwrap_or((s, ""));
+        let host = host.to_string();
+        match (kind.to_lowercase().as_str(), host.is_empty()) {
+            ("github", true) => Ok(Self::Github),
+            ("github-enterprise" | "gitea", false) if !is_valid_host(&host) => {
+                Err(MyError::InvalidSite(s.to_string()))
+            }
+            ("github-enterprise", false) => Ok(Self::GithubEnterprise { host }),
+            ("gitea", false) => Ok(Self::Gitea { host }),
+            ("bitbucket", true) => Ok(Self::Bitbucket),
+            _ => Err(MyError::InvalidSite(s.to_string())),
+        }
+    }
+}
+
+impl Serialize for Site {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Site {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_round_trip() {
+        for (site, s) in [
+            (Site::Github, "github"),
+            (
+                Site::GithubEnterprise {
+                    host: "github.example.com".into(),
+                },
+                "github-enterprise:github.example.com",
+            ),
+            (
+                Site::Gitea {
+                    host: "git.example.com".into(),
+                },
+                "gitea:git.example.com",
+            ),
+            (Site::Bitbucket, "bitbucket"),
+        ] {
+            assert_eq!(site.to_string(), s);
+            assert_eq!(s.parse::<Site>().unwrap(), site);
+            let json = serde_json::to_string(&site).unwrap();
+            assert_eq!(json, format!("\"{s}\""));
+            assert_eq!(serde_json::from_str::<Site>(&json).unwrap(), site);
+        }
+        assert!("gitea".parse::<Site>().is_err());
+        assert!("unknown:host".parse::<Site>().is_err());
+        assert!("gitea:bad host".parse::<Site>().is_err());
+        for host in ["a/b", "a?c", "a#c", "a/b?c"] {
+            let err = format!("github-enterprise:{host}")
+                .parse::<Site>()
+                .unwrap_err();
+            assert_eq!(err.kind(), "invalid_site");
+        }
+        assert_eq!(
+            "gitea:git.example.com:3000"
+                .parse::<Site>()
+                .unwrap()
+                .base()
+                .as_str(),
+            "https://git.example.com:3000/"
+        );
+    }
+
+    #[test]
+    fn test_site_urls() {
+        let site = Site::Gitea {
+            host: "git.example.com".into(),
+        };
+        assert_eq!(site.base().as_str(), "https://git.example.com/");
+        assert_eq!(site.api_base().as_str(), "https://git.example.com/api/v1/");
+        assert_eq!(
+            Site::Bitbucket.api_base().as_str(),
+            "https://api.bitbucket.org/2.0/"
+        );
+        assert_eq!(Site::from_host("bitbucket.org"), Some(Site::Bitbucket));
+        assert_eq!(Site::from_host("git.example.com"), None);
+    }
+
+    #[test]
+    fn test_of_url() {
+        let gitea = Site::Gitea {
+            host: "git.example.com".into(),
+        };
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert_eq!(
+            Site::of_url(&url("https://bitbucket.org/a/b"), &gitea).unwrap(),
+            Site::Bitbucket
+        );
+        assert_eq!(
+            Site::of_url(&url("https://git.example.com/a/b"), &gitea).unwrap(),
+            gitea
+        );
+        let err = Site::of_url(&url("https://codeberg.org/a/b"), &Site::Github).unwrap_err();
+        assert_eq!(err.kind(), "unknown_host");
+        assert!(err.to_string().contains("--source gitea:codeberg.org"));
+        let err = Site::of_url(&url("https://git.example.com:3000/a/b"), &gitea).unwrap_err();
+        assert!(err.to_string().contains("`git.example.com:3000`"));
+    }
+}
@@ -1,8 +1,182 @@
+use chrono::{Local, TimeZone};
+use reqwest::StatusCode;
 use thiserror::Error;
+use url::Url;
 
 #[allow(missing_docs, clippy::missing_docs_in_private_items)]
 #[derive(Error, Debug)]
 pub enum MyError {
     #[error("No available asset found in this repo. If you're sure there's a valid asset, use `--interactive`.")]
     NoAvailableAsset,
+    #[error("{site} API rate limit exceeded; resets in {} (at {at} local). Please retry later.", in_minutes(*.minutes))]
+    RateLimited {
+        site: String,
+        minutes: i64,
+        at: String,
+    },
+    #[error("Unexpected status {status} from {url}")]
+    UnexpectedStatus { status: StatusCode, url: Url },
+    #[error(
+        "{0} has no release. It may only publish source code, or publish its binaries elsewhere."
+    )]
+    NoRelease(String),
+    #[error("Release `{tag}` of {repo} has no downloadable assets.{hint}")]
+    NoReleaseAsset {
+        repo: String,
+        tag: String,
+        hint: String,
+    },
+    #[error("No release of {repo} matches `{spec}`{}.", and_tag_glob(.tag_glob.as_deref()))]
+    NoMatchingRelease {
+        repo: String,
+        spec: String,
+        tag_glob: Option<String>,
+    },
+    #[error("Invalid version requirement: `{0}`, e.g. `^1.2` or `~1.2.3`.")]
+    InvalidReleaseSpec(String),
+    #[error("Repo {0} is not found. Please check the owner and repo name.")]
+    RepoNotFound(String),
+    #[error("Invalid repo: `{0}`. Expected a full name like `owner/repo`, or a repo url.")]
+    InvalidFullName(String),
+    #[error("Unknown site of host `{0}`. If it's a self-hosted Gitea or GitHub Enterprise, please pass `--source gitea:{0}` or `--source github-enterprise:{0}`.")]
+    UnknownHost(String),
+    #[error("No repo found for `{0}`.")]
+    NoRepoFound(String),
+    #[error(
+        "Can't connect to {0}. Please check your network connection, or the proxy if you use one."
+    )]
+    Unreachable(String),
+    #[error("`{0}` needs a search, which `--no-search` disables. Please give a full name like `owner/repo`.")]
+    SearchDisabled(String),
+    #[error(
+        "Invalid site: `{0}`. Expected `github`, `github-enterprise:<host>`, `gitea:<host>` or `bitbucket`."
+    )]
+    InvalidSite(String),
+}
+
+impl MyError {
+    /// A stable name of the error, for machine-readable output.
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::NoAvailableAsset => "no_asset",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::UnexpectedStatus { .. } => "unexpected_status",
+            Self::NoRelease(_) => "no_release",
+            Self::NoReleaseAsset { .. } => "no_release_asset",
+            Self::NoMatchingRelease { .. } => "no_matching_release",
+            Self::InvalidReleaseSpec(_) => "invalid_release_spec",
+            Self::RepoNotFound(_) => "repo_not_found",
+            Self::InvalidFullName(_) => "invalid_full_name",
+            Self::UnknownHost(_) => "unknown_host",
+            Self::NoRepoFound(_) => "no_repo_found",
+            Self::SearchDisabled(_) => "search_disabled",
+            Self::Unreachable(_) => "unreachable",
+            Self::InvalidSite(_) => "invalid_site",
+        }
+    }
+
+    /// Construct a [`MyError::RateLimited`] of `site` from the unix timestamp
+    /// when the rate limit resets, and the current unix timestamp.
+    pub fn rate_limited(site: &str, reset: i64, now: i64) -> Self {
+        let at = Local.timestamp_opt(reset, 0).single().map_or_else(
+            || "unknown time".to_string(),
+            |t| t.format("%H:%M").to_string(),
+        );
+        Self::RateLimited {
+            site: site.to_string(),
+            minutes: (reset - now + 59).div_euclid(60).max(0),
+            at,
+        }
+    }
+}
+
+/// The message of `err` to show the user. An error with a [`MyError`] in its
+/// chain is shown by that message alone, since its causes like the raw
+/// network error only repeat it; `verbose` shows the full chain.
+pub fn error_message(err: &anyhow::Error, verbose: bool) -> String {
+    if verbose {
+        return format!("{err:#}");
+    }
+    err.downcast_ref::<MyError>()
+        .map_or_else(|| format!("{err:#}"), ToString::to_string)
+}
+
+/// How long until the rate limit of [`MyError::RateLimited`] resets, like
+/// `3 minutes`.
+fn in_minutes(minutes: i64) -> String {
+    match minutes {
+        ..=0 => "under a minute".to_string(),
+        1 => "1 minute".to_string(),
+        _ => format!("{minutes} minutes"),
+    }
+}
+
+/// The `--release-tag-glob` part of [`MyError::NoMatchingRelease`].
+fn and_tag_glob(tag_glob: Option<&str>) -> String {
+    tag_glob.map_or_else(String::new, |glob| format!(" and `{glob}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limited() {
+        let err = MyError::rate_limited("GitHub", 1_700_002_040, 1_700_000_000);
+        assert!(err
+            .to_string()
+            .starts_with("GitHub API rate limit exceeded; resets in 34 minutes (at "));
+        let MyError::RateLimited { minutes, .. } = MyError::rate_limited("Gitea", 100, 200) else {
+            unreachable!()
+        };
+        assert_eq!(minutes, 0);
+        let message = |reset| MyError::rate_limited("GitHub", reset, 0).to_string();
+        assert!(message(0).contains("resets in under a minute (at "));
+        assert!(message(30).contains("resets in 1 minute (at "));
+        assert!(message(61).contains("resets in 2 minutes (at "));
+    }
+
+    #[test]
+    fn test_no_matching_release() {
+        let err = |tag_glob: Option<&str>| MyError::NoMatchingRelease {
+            repo: "owner/tool".into(),
+            spec: "latest".into(),
+            tag_glob: tag_glob.map(Into::into),
+        };
+        assert_eq!(
+            err(None).to_string(),
+            "No release of owner/tool matches `latest`."
+        );
+        assert_eq!(
+            err(Some("v1.*")).to_string(),
+            "No release of owner/tool matches `latest` and `v1.*`."
+        );
+    }
+
+    #[test]
+    fn test_error_message() {
+        let err = anyhow::Error::msg("dns error: failed to lookup address")
+            .context(MyError::Unreachable("example.invalid".into()))
+            .context("Failed to get the release");
+        assert_eq!(
+            error_message(&err, false),
+            MyError::Unreachable("example.invalid".into()).to_string()
+        );
+        assert!(error_message(&err, true).ends_with(": dns error: failed to lookup address"));
+        let err = anyhow::Error::msg("invalid json").context("Failed to get the release");
+        assert_eq!(
+            error_message(&err, false),
+            "Failed to get the release: invalid json"
+        );
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(MyError::NoAvailableAsset.kind(), "no_asset");
+        let err = anyhow::Error::from(MyError::NoRepoFound("x".into())).context("searching");
+        assert_eq!(
+            err.downcast_ref::<MyError>().map(MyError::kind),
+            Some("no_repo_found")
+        );
+    }
 }
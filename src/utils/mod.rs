@@ -1,6 +1,10 @@
 pub mod err;
 pub mod filter;
 
+use crate::search::RepoHandler;
+use crate::site::Site;
+use semver::Version;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use url::Url;
 
@@ -47,6 +51,87 @@ impl<'a> UrlJoinAll<'a> for Url {
     }
 }
 
+/// Deduplicate package inputs while keeping their order. Inputs are compared
+/// by [`RepoHandler::package_key`] as parsed on `source`, so `Owner/Repo`,
+/// `owner/repo/` and the GitHub url are the same package, while the Bitbucket
+/// url is not. Inputs that can't be parsed are compared as given. Returns the
+/// kept inputs and the dropped duplicates.
+pub fn dedup_inputs(inputs: Vec<String>, source: &Site) -> (Vec<String>, Vec<String>) {
+    let mut seen = HashSet::new();
+    let (mut kept, mut dropped) = (Vec::new(), Vec::new());
+    for input in inputs {
+        let key = RepoHandler::parse_input(&input, source).map_or_else(
+            |_| (source.clone(), input.clone()),
+            |repo| repo.package_key(),
+        );
+        if seen.insert(key) {
+            kept.push(input);
+        } else {
+            dropped.push(input);
+        }
+    }
+    (kept, dropped)
+}
+
+/// Parse a release tag as semver, ignoring a leading `v`. Returns `None` for
+/// tags that are not semver, like `nightly`.
+pub fn parse_version(tag: &str) -> Option<Version> {
+    let tag = tag.trim();
+    Version::parse(tag.strip_prefix(['v', 'V']).unwrap_or(tag)).ok()
+}
+
+/// Format a size in bytes to a human-readable string, like `1.5 MiB`.
+#[allow(clippy::cast_precision_loss)]
+pub fn fmt_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} {}", UNITS[unit])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Separators of the tokens in an asset name.
+const TOKEN_SEPARATORS: [char; 3] = ['-', '_', '.'];
+
+/// Split an asset name into tokens by `-`, `_` and `.`, like
+/// `tool_linux_amd64_v3` into `tool`, `linux`, `amd64` and `v3`.
+pub fn tokenize(name: &str) -> Vec<&str> {
+    name.split(TOKEN_SEPARATORS)
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// Replace the tokens of an asset name by `map`, case-insensitively, keeping
+/// the separators. So with `amd64=x86_64`, `tool-amd64.zip` becomes
+/// `tool-x86_64.zip`.
+pub fn map_tokens(name: &str, map: &[(String, String)]) -> String {
+    if map.is_empty() {
+        return name.to_string();
+    }
+    name.split_inclusive(TOKEN_SEPARATORS)
+        .map(|part| {
+            let token = part.trim_end_matches(TOKEN_SEPARATORS);
+            let separator = &part[token.len()..];
+            map.iter()
+                .find(|(from, _)| from.eq_ignore_ascii_case(token))
+                .map_or_else(|| part.to_string(), |(_, to)| format!("{to}{separator}"))
+        })
+        .collect()
+}
+
+/// Whether the value of the `CI` environment variable means running in CI,
+/// as set by most CI services.
+pub fn is_ci(value: Option<&str>) -> bool {
+    value.is_some_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
 /// Format a repo as a row in info list.
 pub fn fmt_repo_list<T, U, V>(name: T, url: U, version: V) -> String
 where
@@ -75,4 +160,72 @@ mod tests {
             .unwrap();
         assert_eq!(url.as_str(), "https://codegeex.cn/foo/bar/baz/asdf");
     }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(parse_version("1.2.3"), Some(Version::new(1, 2, 3)));
+        assert!(parse_version("1.2.3-rc.1").is_some_and(|v| !v.pre.is_empty()));
+        assert_eq!(parse_version("nightly"), None);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("tool_linux_amd64_v3.tar.gz"),
+            ["tool", "linux", "amd64", "v3", "tar", "gz"]
+        );
+        let map = [("amd64".to_string(), "x86_64".to_string())];
+        assert_eq!(map_tokens("tool-AMD64.zip", &map), "tool-x86_64.zip");
+        assert_eq!(map_tokens("tool_amd64_v3", &map), "tool_x86_64_v3");
+        assert_eq!(map_tokens("tool-amd6", &map), "tool-amd6");
+    }
+
+    #[test]
+    fn test_is_ci() {
+        assert!(is_ci(Some("true")));
+        assert!(is_ci(Some("1")));
+        assert!(!is_ci(Some("false")));
+        assert!(!is_ci(Some("")));
+        assert!(!is_ci(None));
+    }
+
+    #[test]
+    fn test_fmt_size() {
+        assert_eq!(fmt_size(0), "0 B");
+        assert_eq!(fmt_size(1023), "1023 B");
+        assert_eq!(fmt_size(1536), "1.5 KiB");
+        assert_eq!(fmt_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn test_dedup_inputs() {
+        let inputs = [
+            "eza",
+            "lxl66566/bpm-rs",
+            "eza",
+            "https://github.com/lxl66566/bpm-rs/",
+            "bat",
+            "Lxl66566/bpm-rs",
+        ]
+        .map(std::string::ToString::to_string)
+        .into();
+        let (kept, dropped) = dedup_inputs(inputs, &Site::Github);
+        assert_eq!(kept, vec!["eza", "lxl66566/bpm-rs", "bat"]);
+        assert_eq!(
+            dropped,
+            vec![
+                "eza",
+                "https://github.com/lxl66566/bpm-rs/",
+                "Lxl66566/bpm-rs"
+            ]
+        );
+        // The same full name on another site is another package.
+        let inputs = ["https://bitbucket.org/a/b", "a/b", "a/b@v1.0.0"]
+            .map(std::string::ToString::to_string)
+            .into();
+        let (kept, dropped) = dedup_inputs(inputs, &Site::Github);
+        assert_eq!(kept.len(), 3);
+        assert!(dropped.is_empty());
+    }
 }